@@ -0,0 +1,36 @@
+import java.util.*;
+import java.util.function.*;
+public class Sample<T extends Comparable<T>> implements Runnable, java.io.Serializable {
+    public static final long BIG = 1234567890123L;
+    public static final double PI = 3.14159;
+    public static final float F = 1.5f;
+    public static final int I = 100000;
+    public static final String S = "héllo\u0000 😀 world";
+    private volatile int counter;
+    protected transient List<String> names = new ArrayList<>();
+    short sh; byte b; char c; boolean z;
+    int[][] grid;
+    public Sample() {}
+    @Deprecated
+    public synchronized void run() {
+        Runnable r = () -> counter++;
+        r.run();
+        Function<String, Integer> f = String::length;
+        switch (names.size()) { case 0: counter = 1; break; case 1: counter = 2; break; case 100: counter = 3; break; }
+        switch (counter) { case 1: case 2: case 3: case 4: counter--; break; default: counter = 0; }
+        try { Object o = names.get(0); if (o instanceof String) counter += ((String) o).length(); }
+        catch (IndexOutOfBoundsException | NullPointerException e) { counter = -1; }
+        finally { counter++; }
+        long l = BIG * 2; double d = PI / l; float fl = F; 
+        for (int i = 0; i < 300; i++) { l += i; }
+        String s = "x" + counter + l;
+        int[] arr = new int[5]; Object[] oa = new String[3]; int[][] m = new int[2][3];
+        synchronized (this) { counter ^= 0xFFFF; }
+    }
+    static native void nat(long a, double b, int... rest);
+    interface Inner { default int x() { return 1; } static void y() {} }
+    enum Color { RED, GREEN }
+    record Point(int x, int y) {}
+    public <U> U generic(U u, T t) throws Exception { return u; }
+    String sw(String s) { switch (s) { case "a": return "A"; case "b": return "B"; default: return s; } }
+}
@@ -0,0 +1,94 @@
+// Times reading the sample class with ClassFile::parse, which decodes
+// everything, against ClassReader, which only splits up the constant pool and
+// decodes what is asked for, and against decoding the constant pool one entry
+// at a time from an iterator with parse_constant_pool_tag. Sample.class is
+// built from data/Sample.java with javac 17.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use nonesense::{ClassFile, ClassReader, ConstantPoolItem, parse_constant_pool_tag, parse_u16};
+use std::hint::black_box;
+
+const SAMPLE: &[u8] = include_bytes!("data/Sample.class");
+
+/// Reads the constant pool one entry at a time from an iterator. The
+/// unusable slot after a `Long` or `Double` is `None`. Also returns the
+/// offset of the first byte after the pool.
+fn iterator_constant_pool(input: &[u8]) -> (Vec<Option<ConstantPoolItem>>, usize) {
+    let major_version = parse_u16(&input[6..]);
+    let count = parse_u16(&input[8..]);
+    let mut iter = input[10..].iter().copied();
+    let mut items = Vec::with_capacity(count as usize);
+    let mut index = 1;
+    while index < count {
+        let offset = input.len() - iter.len();
+        let item = parse_constant_pool_tag(&mut iter, offset, index, major_version).unwrap();
+        let wide = matches!(
+            item,
+            ConstantPoolItem::Long(_) | ConstantPoolItem::Double(_)
+        );
+        items.push(Some(item));
+        index += 1;
+        if wide {
+            items.push(None);
+            index += 1;
+        }
+    }
+    (items, input.len() - iter.len())
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("class_file", |b| {
+        b.iter(|| ClassFile::parse(black_box(SAMPLE)).unwrap())
+    });
+    group.bench_function("reader", |b| {
+        b.iter(|| ClassReader::new(black_box(SAMPLE)).unwrap())
+    });
+    group.bench_function("iterator", |b| {
+        b.iter(|| iterator_constant_pool(black_box(SAMPLE)))
+    });
+    group.finish();
+}
+
+fn this_class(c: &mut Criterion) {
+    let mut group = c.benchmark_group("this_class");
+    group.bench_function("class_file", |b| {
+        b.iter(|| {
+            let class_file = ClassFile::parse(black_box(SAMPLE)).unwrap();
+            class_file
+                .constant_pool
+                .class_name(class_file.this_class)
+                .unwrap()
+                .to_string()
+        })
+    });
+    group.bench_function("reader", |b| {
+        b.iter(|| {
+            let reader = ClassReader::new(black_box(SAMPLE)).unwrap();
+            reader
+                .class_name(reader.this_class)
+                .unwrap()
+                .decode()
+                .unwrap()
+        })
+    });
+    group.bench_function("iterator", |b| {
+        b.iter(|| {
+            let input = black_box(SAMPLE);
+            let (items, end) = iterator_constant_pool(input);
+            // this_class follows the access_flags after the pool.
+            let this_class = parse_u16(&input[end + 2..]);
+            let Some(ConstantPoolItem::Class(name_index)) = &items[this_class as usize - 1] else {
+                panic!("this_class is not a class");
+            };
+            let Some(ConstantPoolItem::Utf8(name)) = &items[*name_index as usize - 1] else {
+                panic!("the class name is not a Utf8 entry");
+            };
+            name.to_str_lossy().to_string()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, this_class);
+criterion_main!(benches);
@@ -0,0 +1,772 @@
+use crate::classfile::Parser;
+use crate::error::{ClassFormatError, ConstantPoolError, Structure};
+use crate::{
+    ClassFileVersion, ClassRef, ConstantPool, ConstantPoolItem, InnerClassAccessFlags,
+    Instructions, ParameterAccessFlags,
+};
+
+/// The structure an attribute is attached to. Each attribute is only
+/// recognised in the places listed for it in JVMS table 4.7-C.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AttributeLocation {
+    ClassFile,
+    Field,
+    Method,
+    Code,
+}
+
+/// An attribute along with the constant pool index of its name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeInfo {
+    pub name_index: u16,
+    pub attribute: Attribute,
+}
+
+/// A decoded attribute_info structure. Attributes this crate does not know
+/// about, or that appear somewhere they have no meaning, are kept as `Raw`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Attribute {
+    /// The index of the constant value of a field.
+    ConstantValue(u16),
+    /// The body of a method.
+    Code(Code),
+    /// The checked exceptions a method may throw.
+    Exceptions(Vec<ClassRef>),
+    InnerClasses(Vec<InnerClass>),
+    /// The class enclosing a local or anonymous class, and the index of the
+    /// `CONSTANT_NameAndType` of the enclosing method or 0.
+    EnclosingMethod {
+        class: ClassRef,
+        method_index: u16,
+    },
+    Synthetic,
+    /// The index of the generic signature.
+    Signature(u16),
+    /// The index of the name of the source file.
+    SourceFile(u16),
+    LineNumberTable(Vec<LineNumber>),
+    LocalVariableTable(Vec<LocalVariable>),
+    LocalVariableTypeTable(Vec<LocalVariable>),
+    Deprecated,
+    BootstrapMethods(Vec<BootstrapMethod>),
+    MethodParameters(Vec<MethodParameter>),
+    NestHost(ClassRef),
+    NestMembers(Vec<ClassRef>),
+    PermittedSubclasses(Vec<ClassRef>),
+    /// An attribute that is kept as the bytes of its info item.
+    Raw {
+        name: String,
+        bytes: Vec<u8>,
+    },
+}
+
+/// The contents of a Code attribute.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Code {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    /// The bytecode of the method.
+    pub code: Vec<u8>,
+    /// The exception handlers, in the order they are searched.
+    pub exception_table: Vec<ExceptionHandler>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+/// An entry of the exception table of a Code attribute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExceptionHandler {
+    /// The first pc the handler covers.
+    pub start_pc: u16,
+    /// The pc after the last one the handler covers.
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    /// The class of exceptions caught, or `None` for a handler that catches
+    /// everything, as used for `finally`.
+    pub catch_type: Option<ClassRef>,
+}
+
+/// An entry of the InnerClasses attribute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InnerClass {
+    pub inner_class: ClassRef,
+    /// The index of the `CONSTANT_Class` of the outer class, or 0.
+    pub outer_class_info_index: u16,
+    /// The index of the simple name of the inner class, or 0 when it is
+    /// anonymous.
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: u16,
+}
+
+/// An entry of the LineNumberTable attribute.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineNumber {
+    pub start_pc: u16,
+    pub line_number: u16,
+}
+
+/// An entry of the LocalVariableTable or LocalVariableTypeTable attribute.
+/// For the latter `descriptor_index` points at a signature instead.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LocalVariable {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub index: u16,
+}
+
+/// An entry of the BootstrapMethods attribute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BootstrapMethod {
+    /// The index of the `CONSTANT_MethodHandle` to invoke.
+    pub method_ref: u16,
+    /// The indices of the static arguments.
+    pub arguments: Vec<u16>,
+}
+
+/// An entry of the MethodParameters attribute.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MethodParameter {
+    /// The index of the name of the parameter, or 0 when it has none.
+    pub name_index: u16,
+    pub access_flags: u16,
+}
+
+impl InnerClass {
+    /// The access flags of the inner class.
+    pub fn flags(&self) -> InnerClassAccessFlags {
+        InnerClassAccessFlags::from(self.inner_class_access_flags)
+    }
+}
+
+impl MethodParameter {
+    /// The access flags of the parameter.
+    pub fn flags(&self) -> ParameterAccessFlags {
+        ParameterAccessFlags::from(self.access_flags)
+    }
+}
+
+impl Code {
+    /// Decodes the bytecode of a class file with the given version,
+    /// checking constant pool operands against `constant_pool`.
+    pub fn instructions<'a>(
+        &'a self,
+        constant_pool: &'a ConstantPool,
+        version: ClassFileVersion,
+    ) -> Instructions<'a> {
+        Instructions::new(&self.code, constant_pool, version)
+    }
+}
+
+impl Attribute {
+    /// The name the attribute is stored under in the class file.
+    pub fn name(&self) -> &str {
+        match self {
+            Attribute::ConstantValue(_) => "ConstantValue",
+            Attribute::Code(_) => "Code",
+            Attribute::Exceptions(_) => "Exceptions",
+            Attribute::InnerClasses(_) => "InnerClasses",
+            Attribute::EnclosingMethod { .. } => "EnclosingMethod",
+            Attribute::Synthetic => "Synthetic",
+            Attribute::Signature(_) => "Signature",
+            Attribute::SourceFile(_) => "SourceFile",
+            Attribute::LineNumberTable(_) => "LineNumberTable",
+            Attribute::LocalVariableTable(_) => "LocalVariableTable",
+            Attribute::LocalVariableTypeTable(_) => "LocalVariableTypeTable",
+            Attribute::Deprecated => "Deprecated",
+            Attribute::BootstrapMethods(_) => "BootstrapMethods",
+            Attribute::MethodParameters(_) => "MethodParameters",
+            Attribute::NestHost(_) => "NestHost",
+            Attribute::NestMembers(_) => "NestMembers",
+            Attribute::PermittedSubclasses(_) => "PermittedSubclasses",
+            Attribute::Raw { name, .. } => name,
+        }
+    }
+}
+
+type Decoder = fn(&mut Parser<'_>, &ConstantPool) -> Result<Attribute, ClassFormatError>;
+
+/// A known attribute, the places it is recognised and how to decode it.
+struct Registration {
+    name: &'static str,
+    locations: &'static [AttributeLocation],
+    decode: Decoder,
+}
+
+/// Every attribute this crate decodes.
+const REGISTRY: &[Registration] = &[
+    Registration {
+        name: "ConstantValue",
+        locations: &[AttributeLocation::Field],
+        decode: decode_constant_value,
+    },
+    Registration {
+        name: "Code",
+        locations: &[AttributeLocation::Method],
+        decode: decode_code,
+    },
+    Registration {
+        name: "Exceptions",
+        locations: &[AttributeLocation::Method],
+        decode: |parser, constant_pool| {
+            Ok(Attribute::Exceptions(
+                parser.table(Structure::Attributes, |parser| {
+                    parser.class_ref(Structure::Attributes, constant_pool)
+                })?,
+            ))
+        },
+    },
+    Registration {
+        name: "InnerClasses",
+        locations: &[AttributeLocation::ClassFile],
+        decode: decode_inner_classes,
+    },
+    Registration {
+        name: "EnclosingMethod",
+        locations: &[AttributeLocation::ClassFile],
+        decode: |parser, constant_pool| {
+            let class = parser.class_ref(Structure::Attributes, constant_pool)?;
+            let offset = parser.offset();
+            let method_index = parser.u16(Structure::Attributes)?;
+            if method_index != 0 {
+                constant_pool
+                    .name_and_type(method_index)
+                    .map_err(|e| e.at(Structure::Attributes, offset))?;
+            }
+            Ok(Attribute::EnclosingMethod {
+                class,
+                method_index,
+            })
+        },
+    },
+    Registration {
+        name: "Synthetic",
+        locations: &[
+            AttributeLocation::ClassFile,
+            AttributeLocation::Field,
+            AttributeLocation::Method,
+        ],
+        decode: |_, _| Ok(Attribute::Synthetic),
+    },
+    Registration {
+        name: "Signature",
+        locations: &[
+            AttributeLocation::ClassFile,
+            AttributeLocation::Field,
+            AttributeLocation::Method,
+        ],
+        decode: |parser, constant_pool| Ok(Attribute::Signature(parser.utf8_index(constant_pool)?)),
+    },
+    Registration {
+        name: "SourceFile",
+        locations: &[AttributeLocation::ClassFile],
+        decode: |parser, constant_pool| {
+            Ok(Attribute::SourceFile(parser.utf8_index(constant_pool)?))
+        },
+    },
+    Registration {
+        name: "LineNumberTable",
+        locations: &[AttributeLocation::Code],
+        decode: |parser, _| {
+            Ok(Attribute::LineNumberTable(parser.table(
+                Structure::Attributes,
+                |parser| {
+                    Ok(LineNumber {
+                        start_pc: parser.u16(Structure::Attributes)?,
+                        line_number: parser.u16(Structure::Attributes)?,
+                    })
+                },
+            )?))
+        },
+    },
+    Registration {
+        name: "LocalVariableTable",
+        locations: &[AttributeLocation::Code],
+        decode: |parser, constant_pool| {
+            Ok(Attribute::LocalVariableTable(decode_local_variables(
+                parser,
+                constant_pool,
+            )?))
+        },
+    },
+    Registration {
+        name: "LocalVariableTypeTable",
+        locations: &[AttributeLocation::Code],
+        decode: |parser, constant_pool| {
+            Ok(Attribute::LocalVariableTypeTable(decode_local_variables(
+                parser,
+                constant_pool,
+            )?))
+        },
+    },
+    Registration {
+        name: "Deprecated",
+        locations: &[
+            AttributeLocation::ClassFile,
+            AttributeLocation::Field,
+            AttributeLocation::Method,
+        ],
+        decode: |_, _| Ok(Attribute::Deprecated),
+    },
+    Registration {
+        name: "BootstrapMethods",
+        locations: &[AttributeLocation::ClassFile],
+        decode: decode_bootstrap_methods,
+    },
+    Registration {
+        name: "MethodParameters",
+        locations: &[AttributeLocation::Method],
+        decode: decode_method_parameters,
+    },
+    Registration {
+        name: "NestHost",
+        locations: &[AttributeLocation::ClassFile],
+        decode: |parser, constant_pool| {
+            Ok(Attribute::NestHost(
+                parser.class_ref(Structure::Attributes, constant_pool)?,
+            ))
+        },
+    },
+    Registration {
+        name: "NestMembers",
+        locations: &[AttributeLocation::ClassFile],
+        decode: |parser, constant_pool| {
+            Ok(Attribute::NestMembers(
+                parser.table(Structure::Attributes, |parser| {
+                    parser.class_ref(Structure::Attributes, constant_pool)
+                })?,
+            ))
+        },
+    },
+    Registration {
+        name: "PermittedSubclasses",
+        locations: &[AttributeLocation::ClassFile],
+        decode: |parser, constant_pool| {
+            Ok(Attribute::PermittedSubclasses(
+                parser.table(Structure::Attributes, |parser| {
+                    parser.class_ref(Structure::Attributes, constant_pool)
+                })?,
+            ))
+        },
+    },
+];
+
+impl Parser<'_> {
+    /// Parses a count prefixed table of attribute_info structures attached
+    /// to `location`.
+    pub(crate) fn attributes(
+        &mut self,
+        constant_pool: &ConstantPool,
+        location: AttributeLocation,
+    ) -> Result<Vec<AttributeInfo>, ClassFormatError> {
+        self.table(Structure::Attributes, |parser| {
+            let offset = parser.offset();
+            let name_index = parser.u16(Structure::Attributes)?;
+            let length = parser.u32(Structure::Attributes)? as usize;
+            let name = constant_pool
+                .utf8(name_index)
+                .map_err(|e| e.at(Structure::Attributes, offset))?;
+            let mut info = parser.split(length).ok_or(ClassFormatError::Truncated {
+                structure: Structure::Attributes,
+                offset,
+                index: Some(name_index),
+            })?;
+            let malformed = ClassFormatError::MalformedAttribute {
+                offset,
+                index: name_index,
+            };
+            let registration = REGISTRY.iter().find(|registration| {
+                registration.name == name
+                    && registration.locations.contains(&location)
+                    && parser.version.supports_attribute(name)
+            });
+            let attribute = match registration {
+                Some(registration) => {
+                    let attribute = (registration.decode)(&mut info, constant_pool).map_err(
+                        |error| match error {
+                            // Running out of data means the length of the
+                            // attribute does not match its contents.
+                            ClassFormatError::Truncated { .. } => malformed.clone(),
+                            error => error,
+                        },
+                    )?;
+                    if !info.is_empty() {
+                        return Err(malformed);
+                    }
+                    attribute
+                }
+                None => Attribute::Raw {
+                    name: name.to_string(),
+                    bytes: info.rest(),
+                },
+            };
+            Ok(AttributeInfo {
+                name_index,
+                attribute,
+            })
+        })
+    }
+
+    /// Reads the index of a `CONSTANT_Utf8` entry.
+    fn utf8_index(&mut self, constant_pool: &ConstantPool) -> Result<u16, ClassFormatError> {
+        let offset = self.offset();
+        let index = self.u16(Structure::Attributes)?;
+        constant_pool
+            .utf8(index)
+            .map_err(|e| e.at(Structure::Attributes, offset))?;
+        Ok(index)
+    }
+
+    /// Reads the index of a `CONSTANT_Utf8` entry that may also be 0.
+    fn optional_utf8_index(
+        &mut self,
+        constant_pool: &ConstantPool,
+    ) -> Result<u16, ClassFormatError> {
+        let offset = self.offset();
+        let index = self.u16(Structure::Attributes)?;
+        if index != 0 {
+            constant_pool
+                .utf8(index)
+                .map_err(|e| e.at(Structure::Attributes, offset))?;
+        }
+        Ok(index)
+    }
+}
+
+fn decode_constant_value(
+    parser: &mut Parser<'_>,
+    constant_pool: &ConstantPool,
+) -> Result<Attribute, ClassFormatError> {
+    let offset = parser.offset();
+    let index = parser.u16(Structure::Attributes)?;
+    match constant_pool
+        .get(index)
+        .map_err(|e| e.at(Structure::Attributes, offset))?
+    {
+        ConstantPoolItem::Integer(_)
+        | ConstantPoolItem::Float(_)
+        | ConstantPoolItem::Long(_)
+        | ConstantPoolItem::Double(_)
+        | ConstantPoolItem::String(_) => Ok(Attribute::ConstantValue(index)),
+        _ => Err(ConstantPoolError::UnexpectedType {
+            index,
+            expected: "Integer, Float, Long, Double or String",
+        }
+        .at(Structure::Attributes, offset)),
+    }
+}
+
+fn decode_code(
+    parser: &mut Parser<'_>,
+    constant_pool: &ConstantPool,
+) -> Result<Attribute, ClassFormatError> {
+    let offset = parser.offset();
+    let max_stack = parser.u16(Structure::Attributes)?;
+    let max_locals = parser.u16(Structure::Attributes)?;
+    let code_length = parser.u32(Structure::Attributes)? as usize;
+    // The bytecode may not be empty and every pc has to fit in a u16.
+    if code_length == 0 || code_length > u16::MAX as usize {
+        return Err(ClassFormatError::InvalidCode { offset });
+    }
+    let code = parser
+        .split(code_length)
+        .ok_or(ClassFormatError::Truncated {
+            structure: Structure::Attributes,
+            offset,
+            index: None,
+        })?
+        .rest();
+    let exception_table = parser.table(Structure::Attributes, |parser| {
+        let offset = parser.offset();
+        let start_pc = parser.u16(Structure::Attributes)?;
+        let end_pc = parser.u16(Structure::Attributes)?;
+        let handler_pc = parser.u16(Structure::Attributes)?;
+        let catch_type = match parser.u16(Structure::Attributes)? {
+            0 => None,
+            index => Some(ClassRef {
+                index,
+                name: constant_pool
+                    .class_name(index)
+                    .map_err(|e| e.at(Structure::Attributes, offset + 6))?
+                    .to_string(),
+            }),
+        };
+        if start_pc >= end_pc || end_pc as usize > code_length || handler_pc as usize >= code_length
+        {
+            return Err(ClassFormatError::InvalidCode { offset });
+        }
+        Ok(ExceptionHandler {
+            start_pc,
+            end_pc,
+            handler_pc,
+            catch_type,
+        })
+    })?;
+    let attributes = parser.attributes(constant_pool, AttributeLocation::Code)?;
+    Ok(Attribute::Code(Code {
+        max_stack,
+        max_locals,
+        code,
+        exception_table,
+        attributes,
+    }))
+}
+
+fn decode_inner_classes(
+    parser: &mut Parser<'_>,
+    constant_pool: &ConstantPool,
+) -> Result<Attribute, ClassFormatError> {
+    Ok(Attribute::InnerClasses(parser.table(
+        Structure::Attributes,
+        |parser| {
+            let inner_class = parser.class_ref(Structure::Attributes, constant_pool)?;
+            let offset = parser.offset();
+            let outer_class_info_index = parser.u16(Structure::Attributes)?;
+            if outer_class_info_index != 0 {
+                constant_pool
+                    .class_name(outer_class_info_index)
+                    .map_err(|e| e.at(Structure::Attributes, offset))?;
+            }
+            Ok(InnerClass {
+                inner_class,
+                outer_class_info_index,
+                inner_name_index: parser.optional_utf8_index(constant_pool)?,
+                inner_class_access_flags: parser.u16(Structure::Attributes)?,
+            })
+        },
+    )?))
+}
+
+fn decode_local_variables(
+    parser: &mut Parser<'_>,
+    constant_pool: &ConstantPool,
+) -> Result<Vec<LocalVariable>, ClassFormatError> {
+    parser.table(Structure::Attributes, |parser| {
+        Ok(LocalVariable {
+            start_pc: parser.u16(Structure::Attributes)?,
+            length: parser.u16(Structure::Attributes)?,
+            name_index: parser.utf8_index(constant_pool)?,
+            descriptor_index: parser.utf8_index(constant_pool)?,
+            index: parser.u16(Structure::Attributes)?,
+        })
+    })
+}
+
+fn decode_bootstrap_methods(
+    parser: &mut Parser<'_>,
+    constant_pool: &ConstantPool,
+) -> Result<Attribute, ClassFormatError> {
+    Ok(Attribute::BootstrapMethods(parser.table(
+        Structure::Attributes,
+        |parser| {
+            let offset = parser.offset();
+            let method_ref = parser.u16(Structure::Attributes)?;
+            match constant_pool
+                .get(method_ref)
+                .map_err(|e| e.at(Structure::Attributes, offset))?
+            {
+                ConstantPoolItem::MethodHandle(..) => {}
+                _ => {
+                    return Err(ConstantPoolError::UnexpectedType {
+                        index: method_ref,
+                        expected: "MethodHandle",
+                    }
+                    .at(Structure::Attributes, offset));
+                }
+            }
+            let arguments = parser.table(Structure::Attributes, |parser| {
+                let offset = parser.offset();
+                let index = parser.u16(Structure::Attributes)?;
+                match constant_pool
+                    .get(index)
+                    .map_err(|e| e.at(Structure::Attributes, offset))?
+                {
+                    ConstantPoolItem::Utf8(_)
+                    | ConstantPoolItem::Fieldref(..)
+                    | ConstantPoolItem::Methodref(..)
+                    | ConstantPoolItem::InterfaceMethodref(..)
+                    | ConstantPoolItem::NameAndType(..)
+                    | ConstantPoolItem::InvokeDynamic(..)
+                    | ConstantPoolItem::Module(_)
+                    | ConstantPoolItem::Package(_) => Err(ConstantPoolError::UnexpectedType {
+                        index,
+                        expected: "loadable constant",
+                    }
+                    .at(Structure::Attributes, offset)),
+                    _ => Ok(index),
+                }
+            })?;
+            Ok(BootstrapMethod {
+                method_ref,
+                arguments,
+            })
+        },
+    )?))
+}
+
+fn decode_method_parameters(
+    parser: &mut Parser<'_>,
+    constant_pool: &ConstantPool,
+) -> Result<Attribute, ClassFormatError> {
+    // Unlike every other table this one has a single byte count.
+    let count = parser.u8(Structure::Attributes)?;
+    let mut parameters: Vec<MethodParameter> = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        parameters.push(MethodParameter {
+            name_index: parser.optional_utf8_index(constant_pool)?,
+            access_flags: parser.u16(Structure::Attributes)?,
+        });
+    }
+    Ok(Attribute::MethodParameters(parameters))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClassFile;
+    use crate::testing::ClassBuilder;
+
+    /// The offset the attribute passed to `parse_method_attribute` will
+    /// have, which comes after the items that follow the constant pool, the
+    /// empty fields table and the method's own header.
+    fn method_attribute_offset(class: &mut ClassBuilder) -> usize {
+        class.utf8("run");
+        class.utf8("()V");
+        class.end_of_constant_pool() + 20
+    }
+
+    /// Parses a class with a single method that has `attribute`.
+    fn parse_method_attribute(
+        mut class: ClassBuilder,
+        attribute: Vec<u8>,
+    ) -> Result<Attribute, ClassFormatError> {
+        class.method(0x0001, "run", "()V", &[attribute]);
+        let mut class_file = ClassFile::parse(&class.build())?;
+        Ok(class_file.methods.remove(0).attributes.remove(0).attribute)
+    }
+
+    #[test]
+    fn keeps_unknown_attributes_raw() {
+        let mut class = ClassBuilder::new(52);
+        let custom = class.attribute("Custom", &[1, 2, 3]);
+        assert_eq!(
+            parse_method_attribute(class, custom),
+            Ok(Attribute::Raw {
+                name: "Custom".to_string(),
+                bytes: vec![1, 2, 3],
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_attributes_out_of_place_raw() {
+        // Code means nothing on a field.
+        let mut class = ClassBuilder::new(52);
+        let code = class.code(1, 1, &[0xB1], &[]);
+        let bytes = code[6..].to_vec();
+        class.field(0x0000, "value", "I", &[code]);
+        let class_file = class.parse();
+        assert_eq!(
+            class_file.fields[0].attributes[0].attribute,
+            Attribute::Raw {
+                name: "Code".to_string(),
+                bytes,
+            }
+        );
+
+        // BootstrapMethods only exists from Java 7 on. Its contents do not
+        // even have to make sense before then.
+        let mut class = ClassBuilder::new(50);
+        let attribute = class.attribute("BootstrapMethods", &[0xFF]);
+        class.class_attribute(attribute);
+        let class_file = class.parse();
+        assert_eq!(
+            class_file.attributes[0].attribute,
+            Attribute::Raw {
+                name: "BootstrapMethods".to_string(),
+                bytes: vec![0xFF],
+            }
+        );
+        let mut class = ClassBuilder::new(51);
+        let attribute = class.attribute("BootstrapMethods", &[0, 0]);
+        class.class_attribute(attribute);
+        assert_eq!(
+            class.parse().attributes[0].attribute,
+            Attribute::BootstrapMethods(vec![])
+        );
+    }
+
+    #[test]
+    fn rejects_attributes_whose_length_does_not_match() {
+        // One byte short of the index, and one byte past it.
+        for length in [1, 3] {
+            let mut class = ClassBuilder::new(52);
+            let value = class.integer(1);
+            let mut bytes = value.to_be_bytes().to_vec();
+            bytes.resize(length, 0);
+            let attribute = class.attribute("ConstantValue", &bytes);
+            let index = class.utf8("ConstantValue");
+            class.field(0x0000, "value", "I", &[attribute]);
+            // The attribute follows the field's header in the fields table.
+            let offset = class.end_of_constant_pool() + 18;
+            assert_eq!(
+                ClassFile::parse(&class.build()).err(),
+                Some(ClassFormatError::MalformedAttribute { offset, index }),
+                "{length}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_code_of_the_wrong_length() {
+        for length in [0, 65536] {
+            let mut class = ClassBuilder::new(52);
+            let code = class.code(1, 1, &vec![0; length], &[]);
+            let offset = method_attribute_offset(&mut class) + 6;
+            assert_eq!(
+                parse_method_attribute(class, code),
+                Err(ClassFormatError::InvalidCode { offset }),
+                "{length}"
+            );
+        }
+        let mut class = ClassBuilder::new(52);
+        let code = class.code(1, 1, &[0; 65535], &[]);
+        assert!(parse_method_attribute(class, code).is_ok());
+    }
+
+    #[test]
+    fn checks_the_exception_table() {
+        // nop, nop, return
+        let bytecode = [0x00, 0x00, 0xB1];
+        for entry in [[1, 1, 2, 0], [2, 1, 2, 0], [0, 4, 2, 0], [0, 2, 3, 0]] {
+            let mut class = ClassBuilder::new(52);
+            let code = class.code(1, 1, &bytecode, &[entry]);
+            // The entry follows max_stack, max_locals, the code and the
+            // length of the table.
+            let offset = method_attribute_offset(&mut class) + 6 + 8 + bytecode.len() + 2;
+            assert_eq!(
+                parse_method_attribute(class, code),
+                Err(ClassFormatError::InvalidCode { offset }),
+                "{entry:?}"
+            );
+        }
+        let mut class = ClassBuilder::new(52);
+        let exception = class.class("java/lang/Exception");
+        let code = class.code(1, 1, &bytecode, &[[0, 3, 2, exception]]);
+        let Ok(Attribute::Code(code)) = parse_method_attribute(class, code) else {
+            panic!("the code is valid");
+        };
+        assert_eq!(
+            code.exception_table,
+            [ExceptionHandler {
+                start_pc: 0,
+                end_pc: 3,
+                handler_pc: 2,
+                catch_type: Some(ClassRef {
+                    index: exception,
+                    name: "java/lang/Exception".to_string(),
+                }),
+            }]
+        );
+    }
+}
@@ -0,0 +1,971 @@
+use crate::attribute::{Attribute, AttributeInfo, AttributeLocation, BootstrapMethod, Code};
+use crate::constantpool::fixed_info_length;
+use crate::error::{ClassFormatError, ReadError, Structure};
+use crate::{
+    ClassAccessFlags, ClassFileVersion, ConstantPool, FieldAccessFlags, FieldDescriptor,
+    JavaString, MethodAccessFlags, MethodDescriptor, MethodFlag, SupportedVersions,
+    parse_field_descriptor, parse_method_descriptor,
+};
+
+use std::io::{self, Read};
+
+/// The magic value every class file starts with.
+pub(crate) const MAGIC: [u8; 4] = [0xCA, 0xFE, 0xBA, 0xBE];
+
+/// A complete class file as described by section 4.1 of the Java Virtual
+/// Machine Specification.
+#[derive(Clone, Debug)]
+pub struct ClassFile {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool: ConstantPool,
+    pub access_flags: u16,
+    /// The constant pool index of the class defined by this file.
+    pub this_class: u16,
+    /// The constant pool index of the direct superclass, or 0 for
+    /// `java/lang/Object`.
+    pub super_class: u16,
+    /// The direct superinterfaces, in the order they are declared.
+    pub interfaces: Vec<ClassRef>,
+    pub fields: Vec<FieldInfo>,
+    pub methods: Vec<MethodInfo>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+/// A class or interface named through a `CONSTANT_Class` entry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassRef {
+    /// The index of the `CONSTANT_Class` entry.
+    pub index: u16,
+    /// The internal name the entry resolves to.
+    pub name: String,
+}
+
+/// A field declared by the class, from a field_info structure.
+#[derive(Clone, Debug)]
+pub struct FieldInfo {
+    pub access_flags: u16,
+    pub name_index: u16,
+    /// The name exactly as the constant pool holds it.
+    pub name: JavaString,
+    pub descriptor_index: u16,
+    pub descriptor: FieldDescriptor,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+/// A method declared by the class, from a method_info structure.
+#[derive(Clone, Debug)]
+pub struct MethodInfo {
+    pub access_flags: u16,
+    pub name_index: u16,
+    /// The name exactly as the constant pool holds it.
+    pub name: JavaString,
+    pub descriptor_index: u16,
+    pub descriptor: MethodDescriptor,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+impl ClassFile {
+    /// Parses a complete class file of any version. The whole input must be
+    /// consumed by the class file for it to be accepted.
+    pub fn parse(input: &[u8]) -> Result<ClassFile, ClassFormatError> {
+        ClassFile::parse_supported(input, None)
+    }
+
+    /// Parses a complete class file, rejecting it when its version is not
+    /// one of `versions`.
+    pub fn parse_with_versions(
+        input: &[u8],
+        versions: &SupportedVersions,
+    ) -> Result<ClassFile, ClassFormatError> {
+        ClassFile::parse_supported(input, Some(versions))
+    }
+
+    fn parse_supported(
+        input: &[u8],
+        versions: Option<&SupportedVersions>,
+    ) -> Result<ClassFile, ClassFormatError> {
+        let mut parser = Parser::new(input);
+        parser.magic()?;
+        let minor_version = parser.u16(Structure::Version)?;
+        let major_version = parser.u16(Structure::Version)?;
+        parser.version = ClassFileVersion::new(major_version, minor_version);
+        if let Some(versions) = versions {
+            versions.check(parser.version)?;
+        }
+        let constant_pool_count = parser.u16(Structure::ConstantPool)?;
+        let constant_pool = ConstantPool::parse(&mut parser, constant_pool_count, major_version)?;
+        let access_flags = parser.u16(Structure::AccessFlags)?;
+        constant_pool
+            .validate(major_version, ClassAccessFlags::from(access_flags))
+            .map_err(|violations| ClassFormatError::InvalidConstantPool { violations })?;
+        let this_class = parser.u16(Structure::ThisClass)?;
+        let super_class = parser.u16(Structure::SuperClass)?;
+        let interfaces = parser.interfaces(&constant_pool)?;
+        let fields = parser.fields(&constant_pool)?;
+        let methods = parser.methods(&constant_pool)?;
+        let attributes = parser.attributes(&constant_pool, AttributeLocation::ClassFile)?;
+        parser.end()?;
+        constant_pool
+            .validate_bootstrap_methods(bootstrap_methods(&attributes))
+            .map_err(|violations| ClassFormatError::InvalidConstantPool { violations })?;
+        Ok(ClassFile {
+            minor_version,
+            major_version,
+            constant_pool,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    /// The version of the class file format it uses.
+    pub fn version(&self) -> ClassFileVersion {
+        ClassFileVersion::new(self.major_version, self.minor_version)
+    }
+
+    /// The access flags of the class.
+    pub fn flags(&self) -> ClassAccessFlags {
+        ClassAccessFlags::from(self.access_flags)
+    }
+
+    /// The most bytes [`ClassFile::read_from`] reads.
+    pub const DEFAULT_MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+    /// Reads and parses a class file from a file, pipe, archive entry or
+    /// any other source, reading at most [`ClassFile::DEFAULT_MAX_SIZE`]
+    /// bytes.
+    pub fn read_from(reader: impl Read) -> Result<ClassFile, ReadError> {
+        ClassFile::read_from_with_limit(reader, ClassFile::DEFAULT_MAX_SIZE)
+    }
+
+    /// Reads and parses a class file of at most `max_size` bytes.
+    ///
+    /// The class file is read one structure at a time and each structure is
+    /// checked as soon as it has been read, so reading stops at the first
+    /// one that is not valid: after the magic, the version, the constant
+    /// pool, the interfaces or any field or method. The errors are those of
+    /// [`ClassFile::parse`], and input longer than `max_size` is rejected
+    /// one byte past the limit.
+    pub fn read_from_with_limit(reader: impl Read, max_size: u64) -> Result<ClassFile, ReadError> {
+        ClassFile::read_supported(reader, max_size, None)
+    }
+
+    /// Reads and parses a class file like [`ClassFile::read_from`],
+    /// rejecting it when its version is not one of `versions`, which stops
+    /// the read after the first eight bytes.
+    pub fn read_from_with_versions(
+        reader: impl Read,
+        versions: &SupportedVersions,
+    ) -> Result<ClassFile, ReadError> {
+        ClassFile::read_supported(reader, ClassFile::DEFAULT_MAX_SIZE, Some(versions))
+    }
+
+    /// Reads a class file in the order of [`ClassFile::parse_supported`],
+    /// parsing each structure with the same [`Parser`] methods.
+    fn read_supported(
+        reader: impl Read,
+        max_size: u64,
+        versions: Option<&SupportedVersions>,
+    ) -> Result<ClassFile, ReadError> {
+        let mut stream = StreamParser::new(reader, max_size);
+        stream.parse(MAGIC.len(), |parser| parser.magic())?;
+        let (minor_version, major_version) = stream.parse(4, |parser| {
+            Ok((
+                parser.u16(Structure::Version)?,
+                parser.u16(Structure::Version)?,
+            ))
+        })?;
+        stream.version = ClassFileVersion::new(major_version, minor_version);
+        if let Some(versions) = versions {
+            versions.check(stream.version)?;
+        }
+        let constant_pool_count = stream.parse(2, |parser| parser.u16(Structure::ConstantPool))?;
+        // Each entry is split off as soon as it has been read, which checks
+        // its tag and length. The entries are decoded once the whole pool
+        // has been read, like ClassFile::parse does.
+        let constant_pool_start = stream.position;
+        let mut index: u16 = 1;
+        while index < constant_pool_count {
+            let length = stream.constant_length()?;
+            let tag = stream.parse(length, |parser| {
+                parser
+                    .raw_constant(index, major_version)
+                    .map(|constant| constant.tag)
+            })?;
+            // 8 byte constants take up two entries of the constant pool.
+            index = index.saturating_add(if matches!(tag, 5 | 6) { 2 } else { 1 });
+        }
+        stream.position = constant_pool_start;
+        let constant_pool = stream.parse(0, |parser| {
+            ConstantPool::parse(parser, constant_pool_count, major_version)
+        })?;
+        let access_flags = stream.parse(2, |parser| parser.u16(Structure::AccessFlags))?;
+        constant_pool
+            .validate(major_version, ClassAccessFlags::from(access_flags))
+            .map_err(|violations| ClassFormatError::InvalidConstantPool { violations })?;
+        let (this_class, super_class) = stream.parse(4, |parser| {
+            Ok((
+                parser.u16(Structure::ThisClass)?,
+                parser.u16(Structure::SuperClass)?,
+            ))
+        })?;
+        let interfaces_count = stream.peek(0)?.map_or(0, u16::from_be_bytes);
+        let interfaces = stream.parse(2 + 2 * interfaces_count as usize, |parser| {
+            parser.interfaces(&constant_pool)
+        })?;
+        let fields_count = stream.parse(2, |parser| parser.u16(Structure::Fields))?;
+        let mut fields: Vec<FieldInfo> = Vec::with_capacity(fields_count as usize);
+        for _ in 0..fields_count {
+            // The attributes follow access_flags, name_index and
+            // descriptor_index.
+            let length = stream.attributes_length(6)?;
+            fields.push(stream.parse(length, |parser| parser.field(&constant_pool))?);
+        }
+        let methods_count = stream.parse(2, |parser| parser.u16(Structure::Methods))?;
+        let mut methods: Vec<MethodInfo> = Vec::with_capacity(methods_count as usize);
+        for _ in 0..methods_count {
+            let length = stream.attributes_length(6)?;
+            methods.push(stream.parse(length, |parser| parser.method(&constant_pool))?);
+        }
+        let length = stream.attributes_length(0)?;
+        let attributes = stream.parse(length, |parser| {
+            parser.attributes(&constant_pool, AttributeLocation::ClassFile)
+        })?;
+        // One more byte is enough to tell that the class file goes on.
+        stream.parse(1, |parser| parser.end())?;
+        constant_pool
+            .validate_bootstrap_methods(bootstrap_methods(&attributes))
+            .map_err(|violations| ClassFormatError::InvalidConstantPool { violations })?;
+        Ok(ClassFile {
+            minor_version,
+            major_version,
+            constant_pool,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+}
+
+impl FieldInfo {
+    /// The access flags of the field.
+    pub fn flags(&self) -> FieldAccessFlags {
+        FieldAccessFlags::from(self.access_flags)
+    }
+}
+
+impl MethodInfo {
+    /// The access flags of the method.
+    pub fn flags(&self) -> MethodAccessFlags {
+        MethodAccessFlags::from(self.access_flags)
+    }
+
+    /// The Code attribute of the method, which abstract and native methods
+    /// do not have.
+    pub fn code(&self) -> Option<&Code> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match &attribute.attribute {
+                Attribute::Code(code) => Some(code),
+                _ => None,
+            })
+    }
+}
+
+/// The entries of the BootstrapMethods attribute among the attributes of a
+/// class, if it has one.
+fn bootstrap_methods(attributes: &[AttributeInfo]) -> Option<&[BootstrapMethod]> {
+    attributes
+        .iter()
+        .find_map(|attribute| match &attribute.attribute {
+            Attribute::BootstrapMethods(bootstrap_methods) => Some(&bootstrap_methods[..]),
+            _ => None,
+        })
+}
+
+/// A class file that is read from a source one structure at a time.
+///
+/// Every byte that has been read is kept, and each structure is parsed with
+/// a [`Parser`] over the bytes it was read into, positioned at their offset
+/// in the class file. So the structures are parsed, and fail, exactly as
+/// they do in [`ClassFile::parse`].
+struct StreamParser<R> {
+    reader: io::Take<R>,
+    max_size: u64,
+    /// The bytes that have been read.
+    input: Vec<u8>,
+    /// The number of bytes of `input` that have been parsed.
+    position: usize,
+    /// The version of the class file, once it has been read.
+    version: ClassFileVersion,
+}
+
+impl<R: Read> StreamParser<R> {
+    fn new(reader: R, max_size: u64) -> StreamParser<R> {
+        StreamParser {
+            // Reading one byte past the limit tells a class file of exactly
+            // max_size bytes apart from a larger one.
+            reader: reader.take(max_size.saturating_add(1)),
+            max_size,
+            input: Vec::new(),
+            position: 0,
+            version: ClassFileVersion::latest(),
+        }
+    }
+
+    /// Reads until the input holds `length` bytes past the position, or the
+    /// source ends.
+    fn fill(&mut self, length: usize) -> Result<(), ReadError> {
+        let missing = (self.position + length).saturating_sub(self.input.len());
+        self.reader
+            .by_ref()
+            .take(missing as u64)
+            .read_to_end(&mut self.input)?;
+        if self.input.len() as u64 > self.max_size {
+            return Err(ReadError::TooLarge {
+                max_size: self.max_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// The `N` bytes that start `at` bytes past the position, or `None` when
+    /// the source ends before them.
+    fn peek<const N: usize>(&mut self, at: usize) -> Result<Option<[u8; N]>, ReadError> {
+        self.fill(at + N)?;
+        Ok(self
+            .input
+            .get(self.position + at..)
+            .and_then(|bytes| bytes.first_chunk().copied()))
+    }
+
+    /// Reads the next `length` bytes and parses them with `parse`.
+    fn parse<T>(
+        &mut self,
+        length: usize,
+        parse: impl FnOnce(&mut Parser) -> Result<T, ClassFormatError>,
+    ) -> Result<T, ReadError> {
+        self.fill(length)?;
+        let mut parser = Parser::at(&self.input[self.position..], self.position);
+        parser.version = self.version;
+        let value = parse(&mut parser)?;
+        self.position = parser.offset();
+        Ok(value)
+    }
+
+    /// The length of the constant pool entry at the position, from its tag
+    /// and, for a `Utf8` entry, the length item.
+    fn constant_length(&mut self) -> Result<usize, ReadError> {
+        Ok(match self.peek(0)? {
+            Some([1]) => {
+                3 + self
+                    .peek(1)?
+                    .map_or(0, |length| u16::from_be_bytes(length) as usize)
+            }
+            Some([tag]) => 1 + fixed_info_length(tag).unwrap_or(0),
+            None => 1,
+        })
+    }
+
+    /// The length of a structure that ends in an attributes table, which
+    /// starts `start` bytes past the position. When the source ends early
+    /// the length only goes as far as could be worked out, which is enough
+    /// for the parser to find where the structure is truncated.
+    fn attributes_length(&mut self, start: usize) -> Result<usize, ReadError> {
+        let Some(count) = self.peek(start)? else {
+            return Ok(start + 2);
+        };
+        let mut length = start + 2;
+        for _ in 0..u16::from_be_bytes(count) {
+            // Each attribute starts with attribute_name_index and
+            // attribute_length.
+            match self.peek(length + 2)? {
+                Some(attribute_length) => {
+                    length += 6 + u32::from_be_bytes(attribute_length) as usize
+                }
+                None => return Ok(length + 6),
+            }
+        }
+        Ok(length)
+    }
+}
+
+/// The items shared by the start of field_info and method_info.
+struct MemberHeader<'p> {
+    offset: usize,
+    access_flags: u16,
+    name_index: u16,
+    name: &'p JavaString,
+    descriptor_index: u16,
+    descriptor: &'p JavaString,
+}
+
+/// A cursor over the class file input that keeps track of the current offset
+/// for error reporting.
+#[derive(Clone, Debug)]
+pub(crate) struct Parser<'a> {
+    input: &'a [u8],
+    /// The number of bytes of `input` that have been read.
+    position: usize,
+    /// The offset of `input` in the class file.
+    base: usize,
+    /// The version of the class file, which decides the attributes that
+    /// are recognised.
+    pub(crate) version: ClassFileVersion,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Parser<'a> {
+        Parser::at(input, 0)
+    }
+
+    /// Creates a parser for `input`, which starts at byte `base` of the class
+    /// file.
+    pub(crate) fn at(input: &'a [u8], base: usize) -> Parser<'a> {
+        Parser {
+            input,
+            position: 0,
+            base,
+            version: ClassFileVersion::latest(),
+        }
+    }
+
+    pub(crate) fn offset(&self) -> usize {
+        self.base + self.position
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.position == self.input.len()
+    }
+
+    /// The bytes that have not been read yet.
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        &self.input[self.position..]
+    }
+
+    /// Splits the next `length` bytes off into a parser of their own, or
+    /// returns `None` when there are not that many left.
+    pub(crate) fn split(&mut self, length: usize) -> Option<Parser<'a>> {
+        let input = self.remaining().get(..length)?;
+        let parser = Parser {
+            version: self.version,
+            ..Parser::at(input, self.offset())
+        };
+        self.position += length;
+        Some(parser)
+    }
+
+    /// Takes every byte that is left.
+    pub(crate) fn rest(&mut self) -> Vec<u8> {
+        let rest = self.remaining().to_vec();
+        self.position = self.input.len();
+        rest
+    }
+
+    /// Reads the magic item.
+    fn magic(&mut self) -> Result<(), ClassFormatError> {
+        if self.split(MAGIC.len()).map(|magic| magic.remaining()) != Some(&MAGIC[..]) {
+            return Err(ClassFormatError::BadMagic);
+        }
+        Ok(())
+    }
+
+    /// Checks that the whole class file has been read.
+    fn end(&mut self) -> Result<(), ClassFormatError> {
+        if !self.is_empty() {
+            return Err(ClassFormatError::TrailingBytes {
+                offset: self.offset(),
+            });
+        }
+        Ok(())
+    }
+
+    fn truncated(&self, structure: Structure) -> ClassFormatError {
+        ClassFormatError::Truncated {
+            structure,
+            offset: self.offset(),
+            index: None,
+        }
+    }
+
+    /// Reads the next `N` bytes.
+    fn array<const N: usize>(&mut self, structure: Structure) -> Result<[u8; N], ClassFormatError> {
+        let bytes = self
+            .remaining()
+            .first_chunk::<N>()
+            .copied()
+            .ok_or_else(|| self.truncated(structure))?;
+        self.position += N;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self, structure: Structure) -> Result<u8, ClassFormatError> {
+        self.array::<1>(structure).map(|[byte]| byte)
+    }
+
+    pub(crate) fn u16(&mut self, structure: Structure) -> Result<u16, ClassFormatError> {
+        self.array(structure).map(u16::from_be_bytes)
+    }
+
+    pub(crate) fn u32(&mut self, structure: Structure) -> Result<u32, ClassFormatError> {
+        self.array(structure).map(u32::from_be_bytes)
+    }
+
+    pub(crate) fn u64(&mut self, structure: Structure) -> Result<u64, ClassFormatError> {
+        self.array(structure).map(u64::from_be_bytes)
+    }
+
+    /// Parses a table prefixed with a u16 count, reading each entry with
+    /// `entry`.
+    pub(crate) fn table<T>(
+        &mut self,
+        structure: Structure,
+        mut entry: impl FnMut(&mut Self) -> Result<T, ClassFormatError>,
+    ) -> Result<Vec<T>, ClassFormatError> {
+        let count = self.u16(structure)?;
+        let mut entries: Vec<T> = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            entries.push(entry(self)?);
+        }
+        Ok(entries)
+    }
+
+    /// Reads the index of a `CONSTANT_Class` entry and resolves its name.
+    pub(crate) fn class_ref(
+        &mut self,
+        structure: Structure,
+        constant_pool: &ConstantPool,
+    ) -> Result<ClassRef, ClassFormatError> {
+        let offset = self.offset();
+        let index = self.u16(structure)?;
+        let name = constant_pool
+            .class_name(index)
+            .map_err(|e| e.at(structure, offset))?;
+        Ok(ClassRef {
+            index,
+            name: name.to_string(),
+        })
+    }
+
+    /// Parses the count prefixed interfaces table, resolving every entry to
+    /// the name of a class.
+    fn interfaces(
+        &mut self,
+        constant_pool: &ConstantPool,
+    ) -> Result<Vec<ClassRef>, ClassFormatError> {
+        self.table(Structure::Interfaces, |parser| {
+            parser.class_ref(Structure::Interfaces, constant_pool)
+        })
+    }
+
+    /// Reads the items at the start of a field_info or method_info
+    /// structure and resolves its name and descriptor.
+    fn member_header<'p>(
+        &mut self,
+        structure: Structure,
+        constant_pool: &'p ConstantPool,
+    ) -> Result<MemberHeader<'p>, ClassFormatError> {
+        let offset = self.offset();
+        let access_flags = self.u16(structure)?;
+        let name_index = self.u16(structure)?;
+        let descriptor_index = self.u16(structure)?;
+        let name = constant_pool
+            .java_string(name_index)
+            .map_err(|e| e.at(structure, offset))?;
+        let descriptor = constant_pool
+            .java_string(descriptor_index)
+            .map_err(|e| e.at(structure, offset))?;
+        Ok(MemberHeader {
+            offset,
+            access_flags,
+            name_index,
+            name,
+            descriptor_index,
+            descriptor,
+        })
+    }
+
+    /// Parses the count prefixed table of field_info structures.
+    fn fields(&mut self, constant_pool: &ConstantPool) -> Result<Vec<FieldInfo>, ClassFormatError> {
+        self.table(Structure::Fields, |parser| parser.field(constant_pool))
+    }
+
+    /// Parses a field_info structure.
+    fn field(&mut self, constant_pool: &ConstantPool) -> Result<FieldInfo, ClassFormatError> {
+        let header = self.member_header(Structure::Fields, constant_pool)?;
+        // A descriptor with an unpaired surrogate names no type.
+        let descriptor = header
+            .descriptor
+            .as_str()
+            .and_then(parse_field_descriptor)
+            .ok_or(ClassFormatError::InvalidDescriptor {
+                structure: Structure::Fields,
+                offset: header.offset,
+                index: header.descriptor_index,
+            })?;
+        Ok(FieldInfo {
+            access_flags: header.access_flags,
+            name_index: header.name_index,
+            name: header.name.clone(),
+            descriptor_index: header.descriptor_index,
+            descriptor,
+            attributes: self.attributes(constant_pool, AttributeLocation::Field)?,
+        })
+    }
+
+    /// Parses the count prefixed table of method_info structures.
+    fn methods(
+        &mut self,
+        constant_pool: &ConstantPool,
+    ) -> Result<Vec<MethodInfo>, ClassFormatError> {
+        self.table(Structure::Methods, |parser| parser.method(constant_pool))
+    }
+
+    /// Parses a method_info structure.
+    fn method(&mut self, constant_pool: &ConstantPool) -> Result<MethodInfo, ClassFormatError> {
+        let header = self.member_header(Structure::Methods, constant_pool)?;
+        // A descriptor with an unpaired surrogate names no type, and the
+        // parameters of an instance method share their slots with `this`.
+        let is_static = MethodAccessFlags::from(header.access_flags).contains(MethodFlag::Static);
+        let descriptor = header
+            .descriptor
+            .as_str()
+            .and_then(parse_method_descriptor)
+            .filter(|descriptor| {
+                is_static || descriptor.param_slots() < MethodDescriptor::MAX_PARAM_SLOTS
+            })
+            .ok_or(ClassFormatError::InvalidDescriptor {
+                structure: Structure::Methods,
+                offset: header.offset,
+                index: header.descriptor_index,
+            })?;
+        Ok(MethodInfo {
+            access_flags: header.access_flags,
+            name_index: header.name_index,
+            name: header.name.clone(),
+            descriptor_index: header.descriptor_index,
+            descriptor,
+            attributes: self.attributes(constant_pool, AttributeLocation::Method)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ClassBuilder;
+    use std::io;
+
+    const PACKAGE_INFO: &[u8] = include_bytes!("../../package-info.class");
+
+    /// A source that fails when it is read from.
+    struct Unreadable;
+
+    impl Read for Unreadable {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read past the structure that failed"))
+        }
+    }
+
+    #[test]
+    fn reads_up_to_the_limit() {
+        let size = PACKAGE_INFO.len() as u64;
+        assert!(ClassFile::read_from_with_limit(PACKAGE_INFO, size).is_ok());
+        assert!(matches!(
+            ClassFile::read_from_with_limit(PACKAGE_INFO, size - 1),
+            Err(ReadError::TooLarge { max_size }) if max_size == size - 1
+        ));
+    }
+
+    #[test]
+    fn stops_reading_after_a_bad_magic() {
+        assert!(matches!(
+            ClassFile::read_from(b"\x7fELF".chain(Unreadable)),
+            Err(ReadError::Format(ClassFormatError::BadMagic))
+        ));
+    }
+
+    #[test]
+    fn stops_reading_after_an_unsupported_version() {
+        let java_8 = SupportedVersions {
+            oldest_major: 45,
+            newest_major: 52,
+            preview: false,
+        };
+        let java_17_header: &[u8] = &[0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x3D];
+        assert!(matches!(
+            ClassFile::read_from_with_versions(java_17_header.chain(Unreadable), &java_8),
+            Err(ReadError::Format(ClassFormatError::UnsupportedVersion {
+                major: 61,
+                minor: 0
+            }))
+        ));
+        assert!(ClassFile::read_from_with_versions(PACKAGE_INFO, &java_8).is_ok());
+    }
+
+    #[test]
+    fn stops_reading_after_a_bad_constant_pool() {
+        let mut class = ClassBuilder::new(52);
+        let unknown = class.constant(2, &[]);
+        let input = class.build();
+        let end = class.end_of_constant_pool();
+        assert!(matches!(
+            ClassFile::read_from(input[..end].chain(Unreadable)),
+            Err(ReadError::Format(ClassFormatError::UnknownConstantTag { offset, index, tag: 2 }))
+                if offset == class.offset_of(unknown) && index == unknown
+        ));
+        // A pool that can be read is checked once the access_flags are.
+        let mut class = ClassBuilder::new(52);
+        let integer = class.integer(1);
+        class.reference(7, &[integer]);
+        let input = class.build();
+        let end = class.end_of_constant_pool();
+        assert!(matches!(
+            ClassFile::read_from(input[..end + 2].chain(Unreadable)),
+            Err(ReadError::Format(
+                ClassFormatError::InvalidConstantPool { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn stops_reading_after_a_bad_method() {
+        let mut class = ClassBuilder::new(52);
+        class.method(0x0000, "value", "I", &[]);
+        let index = class.utf8("I");
+        let input = class.build();
+        // The method is the 8 bytes after the empty interfaces and fields
+        // tables and the methods_count, and the class attributes follow it.
+        let offset = class.end_of_constant_pool() + 12;
+        assert!(matches!(
+            ClassFile::read_from(input[..offset + 8].chain(Unreadable)),
+            Err(ReadError::Format(ClassFormatError::InvalidDescriptor {
+                structure: Structure::Methods,
+                offset: found,
+                index: found_index,
+            })) if found == offset && found_index == index
+        ));
+    }
+
+    #[test]
+    fn reads_with_the_errors_of_parse() {
+        let mut class = ClassBuilder::new(52);
+        let code = class.code(1, 1, &[0x2A, 0xB0], &[]);
+        class.method(0x0001, "get", "()Ljava/lang/Object;", &[code]);
+        class.field(0x0002, "value", "J", &[]);
+        let input = class.build();
+        for end in 0..=input.len() {
+            let read = match ClassFile::read_from(&input[..end]) {
+                Ok(class_file) => Ok(class_file.to_bytes()),
+                Err(ReadError::Format(error)) => Err(error),
+                Err(error) => panic!("{error}"),
+            };
+            let parsed = ClassFile::parse(&input[..end]).map(|class_file| class_file.to_bytes());
+            assert_eq!(read, parsed, "{end}");
+        }
+        let mut trailing = input.clone();
+        trailing.push(0);
+        assert!(matches!(
+            ClassFile::read_from(&trailing[..]),
+            Err(ReadError::Format(ClassFormatError::TrailingBytes { offset })) if offset == input.len()
+        ));
+    }
+
+    #[test]
+    fn resolves_the_interfaces() {
+        let mut class = ClassBuilder::new(52);
+        let runnable = class.class("java/lang/Runnable");
+        let serializable = class.class("java/io/Serializable");
+        class.interfaces = vec![runnable, serializable];
+        assert_eq!(
+            class.parse().interfaces,
+            [
+                ClassRef {
+                    index: runnable,
+                    name: "java/lang/Runnable".to_string(),
+                },
+                ClassRef {
+                    index: serializable,
+                    name: "java/io/Serializable".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_interfaces_that_are_not_classes() {
+        let mut class = ClassBuilder::new(52);
+        let runnable = class.class("java/lang/Runnable");
+        let name = class.utf8("java/lang/Runnable");
+        // The second entry follows access_flags, this_class, super_class,
+        // the count of the table and the first entry.
+        let offset = class.end_of_constant_pool() + 10;
+        class.interfaces = vec![runnable, name];
+        assert_eq!(
+            ClassFile::parse(&class.build()).err(),
+            Some(ClassFormatError::UnexpectedConstant {
+                structure: Structure::Interfaces,
+                offset,
+                index: name,
+                expected: "Class",
+            })
+        );
+        class.interfaces = vec![runnable, 0];
+        assert_eq!(
+            ClassFile::parse(&class.build()).err(),
+            Some(ClassFormatError::InvalidConstantIndex {
+                structure: Structure::Interfaces,
+                offset,
+                index: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_fields_and_methods_with_their_attributes() {
+        let mut class = ClassBuilder::new(52);
+        let value = class.integer(42);
+        let constant_value = class.attribute("ConstantValue", &value.to_be_bytes());
+        let deprecated = class.attribute("Deprecated", &[]);
+        class.field(0x0019, "ANSWER", "I", &[constant_value, deprecated]);
+        class.field(0x0002, "names", "[Ljava/lang/String;", &[]);
+        let code = class.code(1, 1, &[0x2A, 0xB1], &[]);
+        let exception = class.class("java/io/IOException");
+        let exceptions = class.attribute("Exceptions", &[0, 1, 0, exception as u8]);
+        class.method(0x0001, "run", "(J[I)V", &[code, exceptions]);
+        let class_file = class.parse();
+
+        let [answer, names] = &class_file.fields[..] else {
+            panic!("expected two fields");
+        };
+        assert_eq!(answer.name, *"ANSWER");
+        assert_eq!(answer.descriptor.to_string(), "int");
+        let attributes: Vec<_> = answer
+            .attributes
+            .iter()
+            .map(|info| &info.attribute)
+            .collect();
+        assert_eq!(
+            attributes,
+            [&Attribute::ConstantValue(value), &Attribute::Deprecated]
+        );
+        assert_eq!(names.name, *"names");
+        assert!(names.attributes.is_empty());
+
+        let [run] = &class_file.methods[..] else {
+            panic!("expected one method");
+        };
+        assert_eq!(run.name, *"run");
+        assert_eq!(run.descriptor.param_slots(), 3);
+        let [code, exceptions] = &run.attributes[..] else {
+            panic!("expected two attributes");
+        };
+        assert!(matches!(&code.attribute, Attribute::Code(code) if code.code == [0x2A, 0xB1]));
+        assert_eq!(
+            exceptions.attribute,
+            Attribute::Exceptions(vec![ClassRef {
+                index: exception,
+                name: "java/io/IOException".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn limits_the_parameter_slots_of_methods() {
+        // An instance method has a slot less for its parameters than a
+        // static one, as `this` takes the first.
+        let slots_254 = format!("({})V", "J".repeat(127));
+        let slots_255 = format!("({}I)V", "J".repeat(127));
+        let slots_256 = format!("({})V", "J".repeat(128));
+        for (access_flags, descriptor, slots) in [
+            (0x0001, &slots_254, Some(254)),
+            (0x0001, &slots_255, None),
+            (0x0009, &slots_255, Some(255)),
+            (0x0009, &slots_256, None),
+        ] {
+            let mut class = ClassBuilder::new(52);
+            class.method(access_flags, "run", descriptor, &[]);
+            let index = class.utf8(descriptor);
+            let result = ClassFile::parse(&class.build());
+            if let Some(slots) = slots {
+                let class_file = result.unwrap();
+                assert_eq!(class_file.methods[0].descriptor.param_slots(), slots);
+            } else {
+                assert_eq!(
+                    result.err(),
+                    Some(ClassFormatError::InvalidDescriptor {
+                        structure: Structure::Methods,
+                        offset: class.end_of_constant_pool() + 12,
+                        index,
+                    }),
+                    "{access_flags:#06x} {descriptor}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_member_names_as_they_are() {
+        let mut class = ClassBuilder::new(52);
+        // "a" followed by an unpaired high surrogate.
+        let name = class.utf8_bytes(&[0x61, 0xED, 0xA0, 0xBD]);
+        let descriptor = class.utf8("I");
+        class.field_at(0x0000, name, descriptor, &[]);
+        let class_file = class.parse();
+        let field = &class_file.fields[0];
+        assert_eq!(field.name.code_units().collect::<Vec<_>>(), [0x61, 0xD83D]);
+        assert_eq!(field.name.to_str_lossy(), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn rejects_members_with_bad_descriptors() {
+        for (descriptor, structure) in [
+            ("V", Structure::Fields),
+            ("Ljava/lang/String", Structure::Fields),
+            ("I", Structure::Methods),
+            ("(V)V", Structure::Methods),
+        ] {
+            let mut class = ClassBuilder::new(52);
+            if structure == Structure::Fields {
+                class.field(0x0000, "value", descriptor, &[]);
+            } else {
+                class.method(0x0000, "value", descriptor, &[]);
+            }
+            let index = class.utf8(descriptor);
+            // The member follows access_flags, this_class, super_class, the
+            // empty interfaces table and the count of its own table, plus
+            // the empty fields table before the methods.
+            let mut offset = class.end_of_constant_pool() + 10;
+            if structure == Structure::Methods {
+                offset += 2;
+            }
+            assert_eq!(
+                ClassFile::parse(&class.build()).err(),
+                Some(ClassFormatError::InvalidDescriptor {
+                    structure,
+                    offset,
+                    index,
+                }),
+                "{descriptor}"
+            );
+        }
+        // A descriptor with an unpaired surrogate does not name a class.
+        let mut class = ClassBuilder::new(52);
+        let name = class.utf8("value");
+        let descriptor = class.utf8_bytes(&[b'L', 0xED, 0xA0, 0xBD, b';']);
+        class.field_at(0x0000, name, descriptor, &[]);
+        assert!(matches!(
+            ClassFile::parse(&class.build()),
+            Err(ClassFormatError::InvalidDescriptor { index, .. }) if index == descriptor
+        ));
+    }
+}
@@ -1,273 +1,1458 @@
+use crate::error::{ClassFormatError, ConstantPoolError, ConstantPoolViolation, Structure};
+use std::fmt;
+
+use crate::classfile::Parser;
+use crate::{
+    BootstrapMethod, CFAccessFlags, ClassAccessFlags, ClassFileVersion, FieldDescriptor,
+    JavaString, MethodDescriptor, parse_field_descriptor, parse_javaized_utf8,
+    parse_method_descriptor, parse_u16,
+};
+
 /// The constant pool is the primary source for symbolic information about the
 /// Class or Interface contained in the class file.
+///
+/// Entries are addressed with the same 1-based indices the class file uses.
+#[derive(Clone, Debug, Default)]
 pub struct ConstantPool {
-    classes: Vec<()>,
-    fields: Vec<()>,
-    class_methods: Vec<()>,
-    interface_methods: Vec<()>,
-    strings: Vec<()>,
-    integers: Vec<()>,
-    floats: Vec<()>,
-    longs: Vec<()>,
-    doubles: Vec<()>,
-    utf8: (),
+    /// Entry `n` of the class file is stored at position `n - 1`. The slot
+    /// following a `Long` or `Double` entry is unusable and holds `None`.
+    items: Vec<Option<ConstantPoolItem>>,
+    /// The byte offset of each entry in the class file it was read from.
+    offsets: Vec<usize>,
 }
 
-#[derive(Clone, Debug)]
-pub enum ConstantPoolItem {
-    Utf8(String),
-    Integer(i32),
-    Float(f32),
-    Long(i64),
-    Double(f64),
-    Class(usize),
-    String(usize),
-    Fieldref(usize, usize),
-    Methodref(usize, usize),
-    InterfaceMethodref(usize, usize),
-    NameAndType(usize, usize),
-    MethodHandle(u8, usize),
-    MethodType(usize),
-    InvokeDynamic(usize, usize),
+/// A resolved `CONSTANT_Fieldref`, `CONSTANT_Methodref` or
+/// `CONSTANT_InterfaceMethodref` entry.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemberRef<'a> {
+    pub class_name: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
 }
 
-impl ConstantPoolItem {
-    pub fn get_string(&self) -> &String {
-        match self {
-            ConstantPoolItem::Utf8(data) => {
-                return data;
-            }
-            _ => {
-                panic!(
-                    "The ConstantPoolItem you called this on doesn't have\
-getString support."
-                );
+impl ConstantPool {
+    /// Reads `count - 1` entries from a class file with the given major
+    /// version, where `count` is the constant_pool_count item. The entries
+    /// are not checked against each other, which is left to
+    /// [`ConstantPool::validate`].
+    pub(crate) fn parse(
+        parser: &mut Parser,
+        count: u16,
+        major_version: u16,
+    ) -> Result<ConstantPool, ClassFormatError> {
+        let constants = parser.raw_constant_pool(count, major_version)?;
+        let mut pool = ConstantPool {
+            items: Vec::with_capacity(constants.len()),
+            offsets: Vec::with_capacity(constants.len()),
+        };
+        let mut offset = 0;
+        for (index, constant) in (1..).zip(&constants) {
+            match constant {
+                Some(constant) => {
+                    offset = constant.offset;
+                    pool.items.push(Some(constant.decode(index)?));
+                }
+                None => pool.items.push(None),
             }
+            pool.offsets.push(offset);
         }
+        Ok(pool)
     }
-}
 
-pub fn parse_constant_pool_tag(iter: &mut impl Iterator<Item = u8>) ->
-Option<ConstantPoolItem> {
-    let mut temp_storage: Vec<u8>;
-    match iter.next() {
-        Some(tag) => match tag {
-            01 => {
-                temp_storage = iter.by_ref().take(2).collect();
-                if temp_storage.len() != 2 {
-                    return None;
-                }
-                let length = parse_u16(&temp_storage[..]);
-                temp_storage = iter.by_ref().take(length as usize).collect();
-                if temp_storage.len() != length as usize {
-                    return None;
-                }
-                match parse_javaized_utf8(&temp_storage) {
-                    Some(temp) => Some(ConstantPoolItem::Utf8(temp)),
-                    None => None,
-                }
-            }
-            03 => {
-                temp_storage = iter.by_ref().take(4).collect();
-                if temp_storage.len() != 4 {
+    /// Checks every entry against the rules of JVMS §4.4 for a class file
+    /// with the given major version and access flags, collecting all of the
+    /// violations found.
+    pub fn validate(
+        &self,
+        major_version: u16,
+        class_flags: ClassAccessFlags,
+    ) -> Result<(), Vec<ConstantPoolViolation>> {
+        let violations: Vec<ConstantPoolViolation> = self
+            .iter()
+            .filter_map(|(index, item)| {
+                let error = self
+                    .check_entry(index, item, major_version, class_flags)
+                    .err()?;
+                Some(ConstantPoolViolation {
+                    index,
+                    offset: self.offset_of(index),
+                    error,
+                })
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Checks that every `CONSTANT_InvokeDynamic` and `CONSTANT_Dynamic` entry
+    /// refers to one of `bootstrap_methods`, the entries of the class file's
+    /// BootstrapMethods attribute or `None` when it has none. This needs the
+    /// attributes of the class, so it is separate from
+    /// [`ConstantPool::validate`].
+    pub fn validate_bootstrap_methods(
+        &self,
+        bootstrap_methods: Option<&[BootstrapMethod]>,
+    ) -> Result<(), Vec<ConstantPoolViolation>> {
+        let violations: Vec<ConstantPoolViolation> = self
+            .iter()
+            .filter_map(|(index, item)| {
+                let (ConstantPoolItem::InvokeDynamic(bootstrap_method, _)
+                | ConstantPoolItem::Dynamic(bootstrap_method, _)) = item
+                else {
                     return None;
+                };
+                let error = match bootstrap_methods {
+                    None => ConstantPoolError::MissingBootstrapMethods {
+                        index,
+                        kind: item.kind(),
+                    },
+                    Some(bootstrap_methods)
+                        if usize::from(*bootstrap_method) >= bootstrap_methods.len() =>
+                    {
+                        ConstantPoolError::InvalidBootstrapMethodIndex {
+                            index,
+                            bootstrap_method: *bootstrap_method,
+                            count: bootstrap_methods.len(),
+                        }
+                    }
+                    Some(_) => return None,
+                };
+                Some(ConstantPoolViolation {
+                    index,
+                    offset: self.offset_of(index),
+                    error,
+                })
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Checks that the indices held by a single entry refer to entries of the
+    /// kinds the specification requires, and that the names and descriptors
+    /// they lead to are well formed.
+    fn check_entry(
+        &self,
+        index: u16,
+        item: &ConstantPoolItem,
+        major_version: u16,
+        class_flags: ClassAccessFlags,
+    ) -> Result<(), ConstantPoolError> {
+        match item {
+            ConstantPoolItem::Utf8(_)
+            | ConstantPoolItem::Integer(_)
+            | ConstantPoolItem::Float(_)
+            | ConstantPoolItem::Long(_)
+            | ConstantPoolItem::Double(_) => Ok(()),
+            ConstantPoolItem::Class(name_index) => {
+                let name = self.utf8(*name_index)?;
+                if !is_class_name(name) {
+                    return Err(ConstantPoolError::InvalidClassName {
+                        index: *name_index,
+                        name: name.to_string(),
+                    });
                 }
-                Some(ConstantPoolItem::Integer(parse_i32(&temp_storage[..])))
+                Ok(())
             }
-            04 => {
-                temp_storage = iter.by_ref().take(4).collect();
-                if temp_storage.len() != 4 {
-                    return None;
-                }
-                Some(ConstantPoolItem::Float(parse_f32(&temp_storage[..])))
+            ConstantPoolItem::String(string_index) => self.utf8(*string_index).map(|_| ()),
+            ConstantPoolItem::MethodType(descriptor_index) => {
+                self.method_descriptor(*descriptor_index).map(|_| ())
             }
-            05 => {
-                temp_storage = iter.by_ref().take(8).collect();
-                if temp_storage.len() != 8 {
-                    return None;
+            ConstantPoolItem::Module(name_index) | ConstantPoolItem::Package(name_index) => {
+                // Only a module-info class describes modules and packages.
+                if !class_flags.contains(CFAccessFlags::Module) {
+                    return Err(ConstantPoolError::OutsideOfModule {
+                        index,
+                        kind: item.kind(),
+                    });
                 }
-                Some(ConstantPoolItem::Long(parse_i64(&temp_storage[..])))
+                self.utf8(*name_index).map(|_| ())
             }
-            06 => {
-                temp_storage = iter.by_ref().take(8).collect();
-                if temp_storage.len() != 8 {
-                    return None;
-                }
-                Some(ConstantPoolItem::Double(parse_f64(&temp_storage[..])))
+            ConstantPoolItem::Fieldref(class_index, name_and_type_index) => {
+                self.class_name(*class_index)?;
+                let (name_index, descriptor_index) =
+                    self.name_and_type_indices(*name_and_type_index)?;
+                self.field_name(name_index)?;
+                self.field_descriptor(descriptor_index).map(|_| ())
             }
-            07 => {
-                temp_storage = iter.by_ref().take(2).collect();
-                if temp_storage.len() != 2 {
-                    return None;
-                }
-                Some(ConstantPoolItem::Class(
-                    parse_u16(&temp_storage[..]) as usize
-                ))
+            ConstantPoolItem::InterfaceMethodref(class_index, name_and_type_index) => {
+                self.class_name(*class_index)?;
+                let (name_index, descriptor_index) =
+                    self.name_and_type_indices(*name_and_type_index)?;
+                // Interfaces are never instantiated, and their initializers
+                // are only run by the Java Virtual Machine itself.
+                self.ordinary_method_name(*name_and_type_index, name_index)?;
+                self.method_descriptor(descriptor_index).map(|_| ())
             }
-            08 => {
-                temp_storage = iter.by_ref().take(2).collect();
-                if temp_storage.len() != 2 {
-                    return None;
+            ConstantPoolItem::Methodref(class_index, name_and_type_index) => {
+                self.class_name(*class_index)?;
+                let (name_index, descriptor_index) =
+                    self.name_and_type_indices(*name_and_type_index)?;
+                let name = self.method_name(name_index)?;
+                // The only special method name a Methodref may use is <init>.
+                if name == "<clinit>" {
+                    return Err(ConstantPoolError::IllegalName {
+                        index: *name_and_type_index,
+                        name: name.to_string(),
+                    });
                 }
-                Some(ConstantPoolItem::String(
-                    parse_u16(&temp_storage[..]) as usize
-                ))
-            }
-            09 => {
-                temp_storage = iter.by_ref().take(4).collect();
-                if temp_storage.len() != 4 {
-                    return None;
+                let descriptor = self.method_descriptor(descriptor_index)?;
+                if name == "<init>" && descriptor.ret.is_some() {
+                    return Err(ConstantPoolError::InvalidDescriptor {
+                        index: descriptor_index,
+                        descriptor: self.utf8(descriptor_index)?.to_string(),
+                        expected: "method descriptor returning void",
+                    });
                 }
-                Some(ConstantPoolItem::Fieldref(
-                    parse_u16(&temp_storage[0..2]) as usize,
-                    parse_u16(&temp_storage[2..]) as usize,
-                ))
+                Ok(())
             }
-            10 => {
-                temp_storage = iter.by_ref().take(4).collect();
-                if temp_storage.len() != 4 {
-                    return None;
+            ConstantPoolItem::NameAndType(name_index, descriptor_index) => {
+                let descriptor = self.utf8(*descriptor_index)?;
+                if parse_field_descriptor(descriptor).is_some() {
+                    self.field_name(*name_index).map(|_| ())
+                } else if parse_method_descriptor(descriptor).is_some() {
+                    self.method_name(*name_index).map(|_| ())
+                } else {
+                    Err(ConstantPoolError::InvalidDescriptor {
+                        index: *descriptor_index,
+                        descriptor: descriptor.to_string(),
+                        expected: "field or method descriptor",
+                    })
                 }
-                Some(ConstantPoolItem::Methodref(
-                    parse_u16(&temp_storage[0..2]) as usize,
-                    parse_u16(&temp_storage[2..]) as usize,
-                ))
             }
-            11 => {
-                temp_storage = iter.by_ref().take(4).collect();
-                if temp_storage.len() != 4 {
-                    return None;
-                }
-                Some(ConstantPoolItem::InterfaceMethodref(
-                    parse_u16(&temp_storage[0..2]) as usize,
-                    parse_u16(&temp_storage[2..]) as usize,
-                ))
+            ConstantPoolItem::MethodHandle(reference_kind, reference_index) => {
+                self.check_method_handle(index, *reference_kind, *reference_index, major_version)
             }
-            12 => {
-                temp_storage = iter.by_ref().take(4).collect();
-                if temp_storage.len() != 4 {
-                    return None;
-                }
-                Some(ConstantPoolItem::NameAndType(
-                    parse_u16(&temp_storage[0..2]) as usize,
-                    parse_u16(&temp_storage[2..]) as usize,
-                ))
+            ConstantPoolItem::InvokeDynamic(_, name_and_type_index) => {
+                let (name_index, descriptor_index) =
+                    self.name_and_type_indices(*name_and_type_index)?;
+                self.ordinary_method_name(*name_and_type_index, name_index)?;
+                self.method_descriptor(descriptor_index).map(|_| ())
             }
-            15 => {
-                temp_storage = iter.by_ref().take(3).collect();
-                if temp_storage.len() != 3 {
-                    return None;
-                }
-                Some(ConstantPoolItem::MethodHandle(
-                    temp_storage[0],
-                    parse_u16(&temp_storage[1..]) as usize,
-                ))
+            ConstantPoolItem::Dynamic(_, name_and_type_index) => {
+                let (name_index, descriptor_index) =
+                    self.name_and_type_indices(*name_and_type_index)?;
+                self.field_name(name_index)?;
+                self.field_descriptor(descriptor_index).map(|_| ())
             }
-            16 => {
-                temp_storage = iter.by_ref().take(2).collect();
-                if temp_storage.len() != 2 {
-                    return None;
-                }
-                Some(ConstantPoolItem::MethodType(
-                    parse_u16(&temp_storage[..]) as usize
-                ))
+        }
+    }
+
+    /// The name and descriptor indices of the `CONSTANT_NameAndType` entry at
+    /// `index`, after checking that both are `CONSTANT_Utf8` entries.
+    fn name_and_type_indices(&self, index: u16) -> Result<(u16, u16), ConstantPoolError> {
+        match self.get(index)? {
+            ConstantPoolItem::NameAndType(name_index, descriptor_index) => {
+                self.utf8(*name_index)?;
+                self.utf8(*descriptor_index)?;
+                Ok((*name_index, *descriptor_index))
             }
-            18 => {
-                temp_storage = iter.by_ref().take(4).collect();
-                if temp_storage.len() != 4 {
-                    return None;
-                }
-                Some(ConstantPoolItem::InvokeDynamic(
-                    parse_u16(&temp_storage[0..2]) as usize,
-                    parse_u16(&temp_storage[2..]) as usize,
-                ))
+            _ => Err(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "NameAndType",
+            }),
+        }
+    }
+
+    /// The field name held by the `CONSTANT_Utf8` entry at `index`, after
+    /// checking that it is an unqualified name.
+    fn field_name(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        let name = self.utf8(index)?;
+        if !is_unqualified_name(name) {
+            return Err(ConstantPoolError::InvalidName {
+                index,
+                name: name.to_string(),
+                expected: "field name",
+            });
+        }
+        Ok(name)
+    }
+
+    /// The method name held by the `CONSTANT_Utf8` entry at `index`, after
+    /// checking that it is an unqualified name without `<` or `>`, or one of
+    /// the special names `<init>` and `<clinit>`.
+    fn method_name(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        let name = self.utf8(index)?;
+        let special = name == "<init>" || name == "<clinit>";
+        if !special && (!is_unqualified_name(name) || name.contains(['<', '>'])) {
+            return Err(ConstantPoolError::InvalidName {
+                index,
+                name: name.to_string(),
+                expected: "method name",
+            });
+        }
+        Ok(name)
+    }
+
+    /// Checks that the method name at `name_index`, used by the
+    /// `CONSTANT_NameAndType` at `name_and_type_index`, is not one of the
+    /// special names.
+    fn ordinary_method_name(
+        &self,
+        name_and_type_index: u16,
+        name_index: u16,
+    ) -> Result<(), ConstantPoolError> {
+        let name = self.method_name(name_index)?;
+        if name.starts_with('<') {
+            return Err(ConstantPoolError::IllegalName {
+                index: name_and_type_index,
+                name: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Decodes the field descriptor held by the `CONSTANT_Utf8` entry at
+    /// `index`.
+    fn field_descriptor(&self, index: u16) -> Result<FieldDescriptor, ConstantPoolError> {
+        let descriptor = self.utf8(index)?;
+        parse_field_descriptor(descriptor).ok_or_else(|| ConstantPoolError::InvalidDescriptor {
+            index,
+            descriptor: descriptor.to_string(),
+            expected: "field descriptor",
+        })
+    }
+
+    /// Decodes the method descriptor held by the `CONSTANT_Utf8` entry at
+    /// `index`.
+    fn method_descriptor(&self, index: u16) -> Result<MethodDescriptor, ConstantPoolError> {
+        let descriptor = self.utf8(index)?;
+        parse_method_descriptor(descriptor).ok_or_else(|| ConstantPoolError::InvalidDescriptor {
+            index,
+            descriptor: descriptor.to_string(),
+            expected: "method descriptor",
+        })
+    }
+
+    /// Checks the rules of JVMS §4.4.8 for a `CONSTANT_MethodHandle` entry.
+    fn check_method_handle(
+        &self,
+        index: u16,
+        reference_kind: u8,
+        reference_index: u16,
+        major_version: u16,
+    ) -> Result<(), ConstantPoolError> {
+        let target = self.get(reference_index)?;
+        let (allowed, expected) = match reference_kind {
+            // REF_getField, REF_getStatic, REF_putField and REF_putStatic
+            1..=4 => (matches!(target, ConstantPoolItem::Fieldref(..)), "Fieldref"),
+            // REF_invokeVirtual and REF_newInvokeSpecial
+            5 | 8 => (
+                matches!(target, ConstantPoolItem::Methodref(..)),
+                "Methodref",
+            ),
+            // REF_invokeStatic and REF_invokeSpecial may only refer to
+            // interface methods from version 52 onwards.
+            6 | 7 if major_version >= 52 => (
+                matches!(
+                    target,
+                    ConstantPoolItem::Methodref(..) | ConstantPoolItem::InterfaceMethodref(..)
+                ),
+                "Methodref or InterfaceMethodref",
+            ),
+            6 | 7 => (
+                matches!(target, ConstantPoolItem::Methodref(..)),
+                "Methodref",
+            ),
+            // REF_invokeInterface
+            9 => (
+                matches!(target, ConstantPoolItem::InterfaceMethodref(..)),
+                "InterfaceMethodref",
+            ),
+            _ => {
+                return Err(ConstantPoolError::InvalidReferenceKind {
+                    index,
+                    kind: reference_kind,
+                });
             }
-            _ => None,
-        },
-        None => None,
+        };
+        if !allowed {
+            return Err(ConstantPoolError::UnexpectedType {
+                index: reference_index,
+                expected,
+            });
+        }
+        let member = self.member_ref(reference_index)?;
+        let legal_name = match reference_kind {
+            1..=4 => true,
+            8 => member.name == "<init>",
+            _ => member.name != "<init>" && member.name != "<clinit>",
+        };
+        if !legal_name {
+            return Err(ConstantPoolError::IllegalName {
+                index: reference_index,
+                name: member.name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The number of slots in the constant pool, including the unusable ones
+    /// after `Long` and `Double` entries. This is one less than the
+    /// constant_pool_count item of the class file.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterates over the usable entries along with their 1-based indices.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &ConstantPoolItem)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(position, item)| Some((position as u16 + 1, item.as_ref()?)))
+    }
+
+    /// The byte offset of the entry at `index` in the class file, or 0 when
+    /// the index is invalid.
+    pub fn offset_of(&self, index: u16) -> usize {
+        match index.checked_sub(1) {
+            Some(position) => self.offsets.get(position as usize).copied().unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    /// Looks up the entry at `index`.
+    pub fn get(&self, index: u16) -> Result<&ConstantPoolItem, ConstantPoolError> {
+        index
+            .checked_sub(1)
+            .and_then(|position| self.items.get(position as usize)?.as_ref())
+            .ok_or(ConstantPoolError::InvalidIndex(index))
     }
-}
 
-pub fn parse_constant_pool(constant_pool: &Vec<ConstantPoolItem>) -> bool {
-    for (ind, t) in constant_pool.iter().enumerate() {
-        match t {
-            ConstantPoolItem::Utf8(data) => {
-                println!("{ind}: I found the raw Utf data: {data}");
+    /// The contents of the `CONSTANT_Utf8` entry at `index`, with unpaired
+    /// surrogates replaced by U+FFFD.
+    pub fn utf8(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        self.java_string(index).map(JavaString::to_str_lossy)
+    }
+
+    /// The exact contents of the `CONSTANT_Utf8` entry at `index`.
+    pub fn java_string(&self, index: u16) -> Result<&JavaString, ConstantPoolError> {
+        match self.get(index)? {
+            ConstantPoolItem::Utf8(data) => Ok(data),
+            _ => Err(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "Utf8",
+            }),
+        }
+    }
+
+    /// The internal name of the `CONSTANT_Class` entry at `index`.
+    pub fn class_name(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match self.get(index)? {
+            ConstantPoolItem::Class(name_index) => self.utf8(*name_index),
+            _ => Err(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "Class",
+            }),
+        }
+    }
+
+    /// The name and descriptor of the `CONSTANT_NameAndType` entry at
+    /// `index`.
+    pub fn name_and_type(&self, index: u16) -> Result<(&str, &str), ConstantPoolError> {
+        match self.get(index)? {
+            ConstantPoolItem::NameAndType(name_index, descriptor_index) => {
+                Ok((self.utf8(*name_index)?, self.utf8(*descriptor_index)?))
             }
-            ConstantPoolItem::Integer(item) => {
-                println!("{ind}: I found the integer {item}.");
+            _ => Err(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "NameAndType",
+            }),
+        }
+    }
+
+    /// The class, name and descriptor of the field or method reference at
+    /// `index`.
+    pub fn member_ref(&self, index: u16) -> Result<MemberRef<'_>, ConstantPoolError> {
+        match self.get(index)? {
+            ConstantPoolItem::Fieldref(class_index, name_and_type_index)
+            | ConstantPoolItem::Methodref(class_index, name_and_type_index)
+            | ConstantPoolItem::InterfaceMethodref(class_index, name_and_type_index) => {
+                let (name, descriptor) = self.name_and_type(*name_and_type_index)?;
+                Ok(MemberRef {
+                    class_name: self.class_name(*class_index)?,
+                    name,
+                    descriptor,
+                })
             }
-            ConstantPoolItem::Float(item) => {
-                println!("{ind}: I found the float {item}.");
+            _ => Err(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "Fieldref, Methodref or InterfaceMethodref",
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ConstantPoolItem {
+    Utf8(JavaString),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(u16),
+    String(u16),
+    Fieldref(u16, u16),
+    Methodref(u16, u16),
+    InterfaceMethodref(u16, u16),
+    NameAndType(u16, u16),
+    MethodHandle(u8, u16),
+    MethodType(u16),
+    InvokeDynamic(u16, u16),
+    Module(u16),
+    Package(u16),
+    Dynamic(u16, u16),
+}
+
+/// The length of the info of a constant pool entry whose size only depends
+/// on its tag, which is every entry but `Utf8`.
+pub(crate) fn fixed_info_length(tag: u8) -> Option<usize> {
+    match tag {
+        7 | 8 | 16 | 19 | 20 => Some(2),
+        15 => Some(3),
+        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => Some(4),
+        5 | 6 => Some(8),
+        _ => None,
+    }
+}
+
+/// Parses the constant pool entry numbered `index`, which starts at byte
+/// `offset` of a class file with the given major version.
+///
+/// The bytes of the entry are collected from the iterator first. Reading
+/// from a slice with [`crate::ClassReader`] or [`crate::ClassFile::parse`]
+/// avoids that.
+pub fn parse_constant_pool_tag(
+    iter: &mut impl Iterator<Item = u8>,
+    offset: usize,
+    index: u16,
+    major_version: u16,
+) -> Result<ConstantPoolItem, ClassFormatError> {
+    let mut entry: Vec<u8> = iter.by_ref().take(1).collect();
+    let length = match entry.first() {
+        Some(&1) => {
+            entry.extend(iter.by_ref().take(2));
+            if entry.len() == 3 {
+                parse_u16(&entry[1..]) as usize
+            } else {
+                0
             }
-            ConstantPoolItem::Long(item) => {
-                println!("{ind}: I found the long {item}.");
+        }
+        Some(&tag) => fixed_info_length(tag).unwrap_or(0),
+        None => 0,
+    };
+    entry.extend(iter.by_ref().take(length));
+    Parser::at(&entry, offset).constant(index, major_version)
+}
+
+/// A constant pool entry that has only been split off the class file, not
+/// decoded.
+#[derive(Clone, Debug)]
+pub(crate) struct RawConstant<'a> {
+    /// The offset of the entry in the class file.
+    pub(crate) offset: usize,
+    pub(crate) tag: u8,
+    /// The bytes after the tag, and after the length of a `Utf8` entry.
+    pub(crate) info: Parser<'a>,
+}
+
+impl RawConstant<'_> {
+    /// Decodes the entry, which is numbered `index`.
+    pub(crate) fn decode(&self, index: u16) -> Result<ConstantPoolItem, ClassFormatError> {
+        // The info was split off with the length its tag calls for, so
+        // reading it can not run out of bytes.
+        let mut info = self.info.clone();
+        let structure = Structure::ConstantPool;
+        Ok(match self.tag {
+            1 => ConstantPoolItem::Utf8(parse_javaized_utf8(info.remaining()).map_err(
+                |position| ClassFormatError::MalformedUtf8 {
+                    offset: info.offset() + position,
+                    index,
+                },
+            )?),
+            3 => ConstantPoolItem::Integer(info.u32(structure)? as i32),
+            4 => ConstantPoolItem::Float(f32::from_bits(info.u32(structure)?)),
+            5 => ConstantPoolItem::Long(info.u64(structure)? as i64),
+            6 => ConstantPoolItem::Double(f64::from_bits(info.u64(structure)?)),
+            7 => ConstantPoolItem::Class(info.u16(structure)?),
+            8 => ConstantPoolItem::String(info.u16(structure)?),
+            9 => ConstantPoolItem::Fieldref(info.u16(structure)?, info.u16(structure)?),
+            10 => ConstantPoolItem::Methodref(info.u16(structure)?, info.u16(structure)?),
+            11 => ConstantPoolItem::InterfaceMethodref(info.u16(structure)?, info.u16(structure)?),
+            12 => ConstantPoolItem::NameAndType(info.u16(structure)?, info.u16(structure)?),
+            15 => ConstantPoolItem::MethodHandle(info.u8(structure)?, info.u16(structure)?),
+            16 => ConstantPoolItem::MethodType(info.u16(structure)?),
+            17 => ConstantPoolItem::Dynamic(info.u16(structure)?, info.u16(structure)?),
+            18 => ConstantPoolItem::InvokeDynamic(info.u16(structure)?, info.u16(structure)?),
+            19 => ConstantPoolItem::Module(info.u16(structure)?),
+            20 => ConstantPoolItem::Package(info.u16(structure)?),
+            tag => {
+                return Err(ClassFormatError::UnknownConstantTag {
+                    offset: self.offset,
+                    index,
+                    tag,
+                });
             }
-            ConstantPoolItem::Double(item) => {
-                println!("{ind}: I found a double {item}.");
+        })
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Splits off the constant pool entry numbered `index` in a class file
+    /// with the given major version, without decoding it.
+    pub(crate) fn raw_constant(
+        &mut self,
+        index: u16,
+        major_version: u16,
+    ) -> Result<RawConstant<'a>, ClassFormatError> {
+        let offset = self.offset();
+        let truncated = || ClassFormatError::Truncated {
+            structure: Structure::ConstantPool,
+            offset,
+            index: Some(index),
+        };
+        let tag = self.u8(Structure::ConstantPool).map_err(|_| truncated())?;
+        if !ClassFileVersion::new(major_version, 0).supports_constant_tag(tag) {
+            return Err(ClassFormatError::ConstantTagNotAllowed {
+                offset,
+                index,
+                tag,
+                major_version,
+            });
+        }
+        let length = match fixed_info_length(tag) {
+            Some(length) => length,
+            None if tag == 1 => {
+                self.u16(Structure::ConstantPool).map_err(|_| truncated())? as usize
             }
-            ConstantPoolItem::Class(name_index) => match
-&constant_pool[*name_index - 1] {
-                ConstantPoolItem::Utf8(name) => {
-                    println!("{ind}: I found a class named: {name}");
-                }
-                _ => {
-                    return false;
+            None => return Err(ClassFormatError::UnknownConstantTag { offset, index, tag }),
+        };
+        let info = self.split(length).ok_or_else(truncated)?;
+        Ok(RawConstant { offset, tag, info })
+    }
+
+    /// Reads and decodes the constant pool entry numbered `index`.
+    pub(crate) fn constant(
+        &mut self,
+        index: u16,
+        major_version: u16,
+    ) -> Result<ConstantPoolItem, ClassFormatError> {
+        self.raw_constant(index, major_version)?.decode(index)
+    }
+
+    /// Splits off the `count - 1` entries of a constant pool, where `count`
+    /// is the constant_pool_count item. The unusable slot after a `Long` or
+    /// `Double` entry is `None`.
+    pub(crate) fn raw_constant_pool(
+        &mut self,
+        count: u16,
+        major_version: u16,
+    ) -> Result<Vec<Option<RawConstant<'a>>>, ClassFormatError> {
+        let mut constants = Vec::with_capacity(count as usize);
+        let mut index: u16 = 1;
+        while index < count {
+            let constant = self.raw_constant(index, major_version)?;
+            // 8 byte constants take up two entries of the constant pool. The
+            // second one must exist but can never be used.
+            let wide = matches!(constant.tag, 5 | 6);
+            let offset = constant.offset;
+            constants.push(Some(constant));
+            index += 1;
+            if wide {
+                if index >= count {
+                    return Err(ClassFormatError::InvalidConstantIndex {
+                        structure: Structure::ConstantPool,
+                        offset,
+                        index,
+                    });
                 }
-            },
-            ConstantPoolItem::String(string_index) => {
-                println!(
-                    "{ind}: I found a string. Its contents are at index
-{string_index}."
-                );
+                constants.push(None);
+                index += 1;
             }
-            ConstantPoolItem::Fieldref(class, name_and_type) => {
-                println!(
-                    "{ind}: I found a field belonging to {class} that is named
-and \
-typed at {name_and_type}."
-                );
+        }
+        Ok(constants)
+    }
+}
+
+impl ConstantPoolItem {
+    /// The name of the kind of entry, as javap shows it.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConstantPoolItem::Utf8(_) => "Utf8",
+            ConstantPoolItem::Integer(_) => "Integer",
+            ConstantPoolItem::Float(_) => "Float",
+            ConstantPoolItem::Long(_) => "Long",
+            ConstantPoolItem::Double(_) => "Double",
+            ConstantPoolItem::Class(_) => "Class",
+            ConstantPoolItem::String(_) => "String",
+            ConstantPoolItem::Fieldref(..) => "Fieldref",
+            ConstantPoolItem::Methodref(..) => "Methodref",
+            ConstantPoolItem::InterfaceMethodref(..) => "InterfaceMethodref",
+            ConstantPoolItem::NameAndType(..) => "NameAndType",
+            ConstantPoolItem::MethodHandle(..) => "MethodHandle",
+            ConstantPoolItem::MethodType(_) => "MethodType",
+            ConstantPoolItem::InvokeDynamic(..) => "InvokeDynamic",
+            ConstantPoolItem::Module(_) => "Module",
+            ConstantPoolItem::Package(_) => "Package",
+            ConstantPoolItem::Dynamic(..) => "Dynamic",
+        }
+    }
+}
+
+impl ConstantPool {
+    /// Renders the value the entry at `index` resolves to, the way javap
+    /// shows it in comments.
+    pub fn resolve(&self, index: u16) -> Result<String, ConstantPoolError> {
+        Ok(match self.get(index)? {
+            ConstantPoolItem::Utf8(data) => escape(data),
+            ConstantPoolItem::Integer(value) => value.to_string(),
+            ConstantPoolItem::Float(value) => format!("{}f", java_float(*value)),
+            ConstantPoolItem::Long(value) => format!("{value}l"),
+            ConstantPoolItem::Double(value) => format!("{}d", java_float(*value)),
+            ConstantPoolItem::Class(_) => quote(self.class_name(index)?),
+            ConstantPoolItem::String(string_index) => escape(self.java_string(*string_index)?),
+            ConstantPoolItem::Fieldref(..)
+            | ConstantPoolItem::Methodref(..)
+            | ConstantPoolItem::InterfaceMethodref(..) => {
+                let member = self.member_ref(index)?;
+                format!(
+                    "{}.{}:{}",
+                    quote(member.class_name),
+                    quote(member.name),
+                    member.descriptor
+                )
             }
-            ConstantPoolItem::Methodref(class, name_and_type) => {
-                println!(
-                    "{ind}: I found a method belonging to {class} that is named
-and \
-typed at {name_and_type}."
-                );
+            ConstantPoolItem::NameAndType(..) => {
+                let (name, descriptor) = self.name_and_type(index)?;
+                format!("{}:{descriptor}", quote(name))
             }
-            ConstantPoolItem::InterfaceMethodref(class, name_and_type) => {
-                println!(
-                    "{ind}: I found an interface method belonging to {class} \
-that is name and typed at {name_and_type}."
-                );
+            ConstantPoolItem::MethodHandle(kind, reference_index) => {
+                format!(
+                    "{} {}",
+                    reference_kind_name(*kind),
+                    self.resolve(*reference_index)?
+                )
             }
-            ConstantPoolItem::NameAndType(name, descriptor) => {
-                println!(
-                    "{ind}: I found a member named at {name} and typed at \
-{descriptor}."
-                );
+            ConstantPoolItem::MethodType(descriptor_index) => {
+                self.utf8(*descriptor_index)?.to_string()
             }
-            ConstantPoolItem::MethodHandle(kind, reference) => {
-                println!(
-                    "{ind}: I found a method handle of {kind} referencing \
-the item at {reference}."
-                );
+            ConstantPoolItem::InvokeDynamic(bootstrap, name_and_type_index)
+            | ConstantPoolItem::Dynamic(bootstrap, name_and_type_index) => {
+                let (name, descriptor) = self.name_and_type(*name_and_type_index)?;
+                format!("#{bootstrap}:{}:{descriptor}", quote(name))
             }
-            ConstantPoolItem::MethodType(description) => {
-                println!("{ind}: I found a method descriped at {description}.");
+            ConstantPoolItem::Module(name_index) | ConstantPoolItem::Package(name_index) => {
+                quote(self.utf8(*name_index)?)
             }
-            ConstantPoolItem::InvokeDynamic(bootstrap, name_and_type) => {
-                println!(
-                    "{ind}: I found a dynamic invocation boostrap method named
-and \
-typed at {name_and_type},"
-                );
-                println!(
-                    "and descriped further at index {bootstrap} of the \
-bootstrap table."
-                );
+        })
+    }
+}
+
+/// Prints the constant pool in the layout of `javap -v`, one entry per line.
+impl fmt::Display for ConstantPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.len().to_string().len() + 3;
+        for (index, item) in self.iter() {
+            let arguments = match item {
+                ConstantPoolItem::Utf8(_)
+                | ConstantPoolItem::Integer(_)
+                | ConstantPoolItem::Float(_)
+                | ConstantPoolItem::Long(_)
+                | ConstantPoolItem::Double(_) => None,
+                ConstantPoolItem::Class(index)
+                | ConstantPoolItem::String(index)
+                | ConstantPoolItem::MethodType(index)
+                | ConstantPoolItem::Module(index)
+                | ConstantPoolItem::Package(index) => Some(format!("#{index}")),
+                ConstantPoolItem::Fieldref(class_index, name_and_type_index)
+                | ConstantPoolItem::Methodref(class_index, name_and_type_index)
+                | ConstantPoolItem::InterfaceMethodref(class_index, name_and_type_index) => {
+                    Some(format!("#{class_index}.#{name_and_type_index}"))
+                }
+                ConstantPoolItem::NameAndType(name_index, descriptor_index) => {
+                    Some(format!("#{name_index}:#{descriptor_index}"))
+                }
+                ConstantPoolItem::MethodHandle(kind, reference_index) => {
+                    Some(format!("{kind}:#{reference_index}"))
+                }
+                ConstantPoolItem::InvokeDynamic(bootstrap, name_and_type_index)
+                | ConstantPoolItem::Dynamic(bootstrap, name_and_type_index) => {
+                    Some(format!("#{bootstrap}:#{name_and_type_index}"))
+                }
+            };
+            let value = self
+                .resolve(index)
+                .unwrap_or_else(|error| format!("<{error}>"));
+            let line = match arguments {
+                Some(arguments) => {
+                    let line = format!(
+                        "{:>width$} = {:<18} {arguments}",
+                        format!("#{index}"),
+                        item.kind()
+                    );
+                    // javap lines up the comments in a single column.
+                    format!("{line:<41} // {value}")
+                }
+                None => format!(
+                    "{:>width$} = {:<18} {value}",
+                    format!("#{index}"),
+                    item.kind()
+                ),
+            };
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The javap name of a `CONSTANT_MethodHandle` reference kind.
+pub(crate) fn reference_kind_name(kind: u8) -> &'static str {
+    match kind {
+        1 => "REF_getField",
+        2 => "REF_getStatic",
+        3 => "REF_putField",
+        4 => "REF_putStatic",
+        5 => "REF_invokeVirtual",
+        6 => "REF_invokeStatic",
+        7 => "REF_invokeSpecial",
+        8 => "REF_newInvokeSpecial",
+        9 => "REF_invokeInterface",
+        _ => "REF_unknown",
+    }
+}
+
+/// Whether `name` is a class or interface name in internal form (JVMS
+/// §4.2.1), such as `java/lang/Thread`, or the descriptor of an array type.
+fn is_class_name(name: &str) -> bool {
+    if name.starts_with('[') {
+        return parse_field_descriptor(name).is_some();
+    }
+    name.split('/')
+        .all(|identifier| !identifier.is_empty() && !identifier.contains(['.', ';', '[']))
+}
+
+/// Whether `name` is an unqualified name of a field or method (JVMS §4.2.2).
+fn is_unqualified_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['.', ';', '[', '/'])
+}
+
+/// Quotes a name unless it is a plain sequence of Java identifiers separated
+/// by slashes, like javap does.
+pub(crate) fn quote(name: &str) -> String {
+    let plain = name.split('/').all(|segment| {
+        let mut chars = segment.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    });
+    if plain {
+        name.to_string()
+    } else {
+        format!("\"{name}\"")
+    }
+}
+
+/// Escapes the control characters in a string constant.
+fn escape(data: &JavaString) -> String {
+    let mut escaped = String::new();
+    for decoded in data.chars() {
+        match decoded {
+            Ok('\t') => escaped.push_str("\\t"),
+            Ok('\n') => escaped.push_str("\\n"),
+            Ok('\r') => escaped.push_str("\\r"),
+            Ok('\u{8}') => escaped.push_str("\\b"),
+            Ok('\u{c}') => escaped.push_str("\\f"),
+            Ok(c) if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            Ok(c) => escaped.push(c),
+            Err(unit) => escaped.push_str(&format!("\\u{unit:04x}")),
+        }
+    }
+    escaped
+}
+
+/// Formats a floating point constant the way Java prints it, switching to
+/// computerized scientific notation outside of 10^-3 to 10^7.
+fn java_float<F: Copy + Into<f64> + fmt::Debug + fmt::LowerExp>(value: F) -> String {
+    let magnitude: f64 = value.into().abs();
+    if magnitude.is_nan() {
+        "NaN".to_string()
+    } else if magnitude.is_infinite() {
+        if value.into() > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+        .to_string()
+    } else if magnitude != 0.0 && !(1e-3..1e7).contains(&magnitude) {
+        let formatted = format!("{value:e}");
+        let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+        if mantissa.contains('.') {
+            format!("{mantissa}E{exponent}")
+        } else {
+            format!("{mantissa}.0E{exponent}")
+        }
+    } else {
+        format!("{value:?}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClassFile;
+    use crate::testing::ClassBuilder;
+
+    #[test]
+    fn looks_up_entries_by_kind() {
+        let mut class = ClassBuilder::new(52);
+        let field = class.member_ref(9, "Test", "count", "I");
+        let constant_pool = class.constant_pool();
+        assert_eq!(constant_pool.len(), field as usize);
+        assert_eq!(constant_pool.utf8(1), Ok("Test"));
+        assert_eq!(constant_pool.class_name(class.this_class), Ok("Test"));
+        assert_eq!(
+            constant_pool.member_ref(field),
+            Ok(MemberRef {
+                class_name: "Test",
+                name: "count",
+                descriptor: "I",
+            })
+        );
+        assert_eq!(constant_pool.name_and_type(field - 1), Ok(("count", "I")));
+    }
+
+    #[test]
+    fn rejects_entries_of_the_wrong_kind() {
+        let mut class = ClassBuilder::new(52);
+        let field = class.member_ref(9, "Test", "count", "I");
+        let constant_pool = class.constant_pool();
+        let unexpected = |index, expected| ConstantPoolError::UnexpectedType { index, expected };
+        assert_eq!(constant_pool.utf8(2), Err(unexpected(2, "Utf8")));
+        assert_eq!(constant_pool.java_string(2), Err(unexpected(2, "Utf8")));
+        assert_eq!(constant_pool.class_name(1), Err(unexpected(1, "Class")));
+        assert_eq!(
+            constant_pool.name_and_type(field),
+            Err(unexpected(field, "NameAndType"))
+        );
+        assert_eq!(
+            constant_pool.member_ref(2),
+            Err(unexpected(2, "Fieldref, Methodref or InterfaceMethodref"))
+        );
+    }
+
+    #[test]
+    fn rejects_indices_outside_of_the_pool() {
+        let constant_pool = ClassBuilder::new(52).constant_pool();
+        let past_the_end = constant_pool.len() as u16 + 1;
+        for index in [0, past_the_end, u16::MAX] {
+            assert_eq!(
+                constant_pool.get(index).err(),
+                Some(ConstantPoolError::InvalidIndex(index))
+            );
+            assert_eq!(
+                constant_pool.utf8(index),
+                Err(ConstantPoolError::InvalidIndex(index))
+            );
+            assert_eq!(
+                constant_pool.class_name(index),
+                Err(ConstantPoolError::InvalidIndex(index))
+            );
+            assert_eq!(
+                constant_pool.resolve(index),
+                Err(ConstantPoolError::InvalidIndex(index))
+            );
+            assert_eq!(constant_pool.offset_of(index), 0);
+        }
+    }
+
+    #[test]
+    fn prints_the_pool_like_javap() {
+        let mut class = ClassBuilder::new(52);
+        class.integer(-7);
+        class.constant(4, &1.5f32.to_be_bytes());
+        class.long(1 << 40);
+        class.double(1e-5);
+        class.string("tab\there \"q\"");
+        class.member_ref(9, "Test", "count", "I");
+        let init = class.member_ref(10, "java/lang/Object", "<init>", "()V");
+        class.member_ref(11, "java/lang/Runnable", "run", "()V");
+        let [high, low] = init.to_be_bytes();
+        class.constant(15, &[8, high, low]);
+        let descriptor = class.utf8("(I)V");
+        class.reference(16, &[descriptor]);
+        assert_eq!(
+            class.constant_pool().to_string(),
+            r#"   #1 = Utf8               Test
+   #2 = Class              #1             // Test
+   #3 = Utf8               java/lang/Object
+   #4 = Class              #3             // java/lang/Object
+   #5 = Integer            -7
+   #6 = Float              1.5f
+   #7 = Long               1099511627776l
+   #9 = Double             1.0E-5d
+  #11 = Utf8               tab\there "q"
+  #12 = String             #11            // tab\there "q"
+  #13 = Utf8               count
+  #14 = Utf8               I
+  #15 = NameAndType        #13:#14        // count:I
+  #16 = Fieldref           #2.#15         // Test.count:I
+  #17 = Utf8               <init>
+  #18 = Utf8               ()V
+  #19 = NameAndType        #17:#18        // "<init>":()V
+  #20 = Methodref          #4.#19         // java/lang/Object."<init>":()V
+  #21 = Utf8               java/lang/Runnable
+  #22 = Class              #21            // java/lang/Runnable
+  #23 = Utf8               run
+  #24 = NameAndType        #23:#18        // run:()V
+  #25 = InterfaceMethodref #22.#24        // java/lang/Runnable.run:()V
+  #26 = MethodHandle       8:#20          // REF_newInvokeSpecial java/lang/Object."<init>":()V
+  #27 = Utf8               (I)V
+  #28 = MethodType         #27            // (I)V
+"#
+        );
+    }
+
+    #[test]
+    fn resolves_entries_for_comments() {
+        let mut class = ClassBuilder::new(52);
+        let nan = class.constant(4, &f32::NAN.to_be_bytes());
+        let infinity = class.double(f64::NEG_INFINITY);
+        let large = class.constant(4, &1.0e10f32.to_be_bytes());
+        let text = class.string("line\n\u{1}\u{e9}");
+        let surrogate = class.utf8_bytes(&[0x41, 0xED, 0xA0, 0xBD]);
+        let constant_pool = class.constant_pool();
+        assert_eq!(constant_pool.resolve(nan).as_deref(), Ok("NaNf"));
+        assert_eq!(constant_pool.resolve(infinity).as_deref(), Ok("-Infinityd"));
+        assert_eq!(constant_pool.resolve(large).as_deref(), Ok("1.0E10f"));
+        assert_eq!(
+            constant_pool.resolve(text).as_deref(),
+            Ok("line\\n\\u0001\u{e9}")
+        );
+        assert_eq!(constant_pool.resolve(surrogate).as_deref(), Ok("A\\ud83d"));
+    }
+
+    #[test]
+    fn skips_the_slot_after_8_byte_constants() {
+        let mut class = ClassBuilder::new(52);
+        let long = class.long(-2);
+        let after_long = class.utf8("after long");
+        let double = class.double(0.5);
+        let after_double = class.integer(3);
+        assert_eq!((after_long, after_double), (long + 2, double + 2));
+        let constant_pool = class.constant_pool();
+        assert!(matches!(
+            constant_pool.get(long),
+            Ok(ConstantPoolItem::Long(-2))
+        ));
+        assert_eq!(constant_pool.utf8(after_long), Ok("after long"));
+        assert!(matches!(
+            constant_pool.get(double),
+            Ok(ConstantPoolItem::Double(0.5))
+        ));
+        assert!(matches!(
+            constant_pool.get(after_double),
+            Ok(ConstantPoolItem::Integer(3))
+        ));
+        assert_eq!(
+            constant_pool.offset_of(after_long),
+            class.offset_of(after_long)
+        );
+        // The unusable slots are errors, not the entries after them.
+        for reserved in [long + 1, double + 1] {
+            assert_eq!(
+                constant_pool.get(reserved).err(),
+                Some(ConstantPoolError::InvalidIndex(reserved))
+            );
+        }
+        assert_eq!(
+            constant_pool.utf8(long + 1),
+            Err(ConstantPoolError::InvalidIndex(long + 1))
+        );
+        let indices: Vec<u16> = constant_pool.iter().map(|(index, _)| index).collect();
+        assert!(!indices.contains(&(long + 1)) && !indices.contains(&(double + 1)));
+    }
+
+    #[test]
+    fn needs_room_for_the_slot_after_a_long() {
+        let mut class = ClassBuilder::new(52);
+        let long = class.long(1);
+        // A Long in the last usable slot, with its second slot at
+        // constant_pool_count - 1, is fine.
+        let constant_pool = class.constant_pool();
+        assert_eq!(constant_pool.len(), long as usize + 1);
+        assert!(constant_pool.get(long + 1).is_err());
+        // With a constant_pool_count one lower the second slot is missing,
+        // which is an error rather than a read past the constant pool.
+        let mut input = class.build();
+        input[8..10].copy_from_slice(&(long + 1).to_be_bytes());
+        assert_eq!(
+            ClassFile::parse(&input).err(),
+            Some(ClassFormatError::InvalidConstantIndex {
+                structure: Structure::ConstantPool,
+                offset: class.offset_of(long),
+                index: long + 1,
+            })
+        );
+    }
+
+    /// Parses a single entry, numbered 1, that starts at offset 10.
+    fn parse_entry(bytes: &[u8], major_version: u16) -> Result<ConstantPoolItem, ClassFormatError> {
+        let mut iter = bytes.iter().copied();
+        let item = parse_constant_pool_tag(&mut iter, 10, 1, major_version);
+        // Only the bytes of the entry are taken.
+        assert_eq!(iter.len(), bytes.len().min(1) * 2);
+        item
+    }
+
+    #[test]
+    fn parses_the_newer_constant_tags() {
+        let parse = |bytes: &[u8], major_version| {
+            let mut input = bytes.to_vec();
+            input.extend_from_slice(&[0xAA, 0xBB]);
+            format!("{:?}", parse_entry(&input, major_version).unwrap())
+        };
+        assert_eq!(parse(&[15, 6, 0, 3], 51), "MethodHandle(6, 3)");
+        assert_eq!(parse(&[16, 0, 4], 51), "MethodType(4)");
+        assert_eq!(parse(&[17, 0, 1, 0, 5], 55), "Dynamic(1, 5)");
+        assert_eq!(parse(&[18, 0, 2, 0, 6], 51), "InvokeDynamic(2, 6)");
+        assert_eq!(parse(&[19, 0, 7], 53), "Module(7)");
+        assert_eq!(parse(&[20, 0, 8], 53), "Package(8)");
+    }
+
+    #[test]
+    fn gates_constant_tags_by_major_version() {
+        let not_allowed = |tag, major_version| {
+            Err(ClassFormatError::ConstantTagNotAllowed {
+                offset: 10,
+                index: 1,
+                tag,
+                major_version,
+            })
+        };
+        let entries: [(&[u8], u16); 6] = [
+            (&[15, 6, 0, 3], 51),
+            (&[16, 0, 4], 51),
+            (&[17, 0, 1, 0, 5], 55),
+            (&[18, 0, 2, 0, 6], 51),
+            (&[19, 0, 7], 53),
+            (&[20, 0, 8], 53),
+        ];
+        for (bytes, first_major_version) in entries {
+            let tag = bytes[0];
+            let mut input = bytes.to_vec();
+            input.extend_from_slice(&[0xAA, 0xBB]);
+            assert!(parse_entry(&input, first_major_version).is_ok());
+            assert!(parse_entry(&input, ClassFileVersion::LATEST_MAJOR).is_ok());
+            let older = first_major_version - 1;
+            let error = parse_constant_pool_tag(&mut input.iter().copied(), 10, 1, older);
+            assert_eq!(
+                format!("{error:?}"),
+                format!("{:?}", not_allowed(tag, older) as Result<(), _>)
+            );
+        }
+        // The tags that are older than Java 7 are always allowed.
+        assert!(parse_entry(&[7, 0, 1, 0xAA, 0xBB], 45).is_ok());
+    }
+
+    #[test]
+    fn reports_truncated_and_unknown_entries() {
+        assert_eq!(
+            parse_constant_pool_tag(&mut [17u8, 0, 1].into_iter(), 10, 1, 55).err(),
+            Some(ClassFormatError::Truncated {
+                structure: Structure::ConstantPool,
+                offset: 10,
+                index: Some(1),
+            })
+        );
+        assert_eq!(
+            parse_constant_pool_tag(&mut [21u8, 0, 1].into_iter(), 10, 1, 55).err(),
+            Some(ClassFormatError::UnknownConstantTag {
+                offset: 10,
+                index: 1,
+                tag: 21,
+            })
+        );
+        assert_eq!(
+            parse_constant_pool_tag(&mut [].into_iter(), 10, 1, 55).err(),
+            Some(ClassFormatError::Truncated {
+                structure: Structure::ConstantPool,
+                offset: 10,
+                index: Some(1),
+            })
+        );
+    }
+
+    #[test]
+    fn reports_every_violation_at_once() {
+        let mut class = ClassBuilder::new(55);
+        let bad_name = class.utf8("java.lang.Object");
+        let class_entry = class.reference(7, &[bad_name]);
+        let field = class.member_ref(9, "Test", "f", "()V");
+        let constructor = class.member_ref(10, "Test", "<init>", "()I");
+        let int = class.utf8("I");
+        let method_type = class.reference(16, &[int]);
+        let name_and_type = class.name_and_type("x", "I");
+        let invoke_dynamic = class.reference(18, &[0, name_and_type]);
+        let name_and_type = class.name_and_type("y", "()V");
+        let dynamic = class.reference(17, &[0, name_and_type]);
+        let bad_name_and_type = class.name_and_type("z", "Q");
+        let package = class.reference(20, &[bad_name]);
+        // The Utf8 entries are shared, so these are the existing ones.
+        let void_method = class.utf8("()V");
+        let int_method = class.utf8("()I");
+        let unknown_type = class.utf8("Q");
+        let violation = |index, error| ConstantPoolViolation {
+            index,
+            offset: class.offset_of(index),
+            error,
+        };
+        let descriptor = |index, descriptor: &str, expected| ConstantPoolError::InvalidDescriptor {
+            index,
+            descriptor: descriptor.to_string(),
+            expected,
+        };
+        let expected = vec![
+            violation(
+                class_entry,
+                ConstantPoolError::InvalidClassName {
+                    index: bad_name,
+                    name: "java.lang.Object".to_string(),
+                },
+            ),
+            violation(field, descriptor(void_method, "()V", "field descriptor")),
+            violation(
+                constructor,
+                descriptor(int_method, "()I", "method descriptor returning void"),
+            ),
+            violation(method_type, descriptor(int, "I", "method descriptor")),
+            violation(invoke_dynamic, descriptor(int, "I", "method descriptor")),
+            violation(dynamic, descriptor(void_method, "()V", "field descriptor")),
+            violation(
+                bad_name_and_type,
+                descriptor(unknown_type, "Q", "field or method descriptor"),
+            ),
+            violation(
+                package,
+                ConstantPoolError::OutsideOfModule {
+                    index: package,
+                    kind: "Package",
+                },
+            ),
+        ];
+        let error = ClassFile::parse(&class.build()).unwrap_err();
+        assert_eq!(
+            error,
+            ClassFormatError::InvalidConstantPool {
+                violations: expected
             }
+        );
+        assert_eq!(
+            (error.index(), error.offset()),
+            (Some(class_entry), class.offset_of(class_entry))
+        );
+    }
+
+    #[test]
+    fn accepts_valid_names_descriptors_and_modules() {
+        let mut class = ClassBuilder::new(55);
+        class.class("[[Ljava/lang/String;");
+        class.class("Outer$Inner");
+        class.member_ref(9, "Test", "values", "[J");
+        class.member_ref(10, "Test", "<init>", "(ILjava/lang/String;)V");
+        class.member_ref(11, "java/util/List", "size", "()I");
+        // Field names may use < and >, and NameAndType entries may name a
+        // class initializer.
+        class.member_ref(9, "Test", "<generic>", "I");
+        class.name_and_type("<clinit>", "()V");
+        let name_and_type = class.name_and_type("lambda", "()Ljava/lang/Runnable;");
+        class.reference(18, &[0, name_and_type]);
+        let bootstrap_methods = bootstrap_methods(&mut class, 1);
+        class.class_attribute(bootstrap_methods);
+        assert!(ClassFile::parse(&class.build()).is_ok());
+        for name in ["", "a.b", "a;", "[a", "a/b"] {
+            assert!(!is_unqualified_name(name), "{name:?}");
+        }
+        for name in [
+            "",
+            "a//b",
+            "/a",
+            "a/",
+            "a;",
+            "[",
+            "[V",
+            "[Ljava/lang/String",
+        ] {
+            assert!(!is_class_name(name), "{name:?}");
+        }
+        // Module and Package entries belong in module-info classes.
+        let mut module = ClassBuilder::new(53);
+        module.access_flags = 0x8000;
+        let name = module.utf8("java.base");
+        module.reference(19, &[name]);
+        let name = module.utf8("java/lang");
+        module.reference(20, &[name]);
+        assert!(ClassFile::parse(&module.build()).is_ok());
+    }
+
+    /// Builds a BootstrapMethods attribute with `count` entries, which all
+    /// invoke a static method without arguments.
+    fn bootstrap_methods(class: &mut ClassBuilder, count: u16) -> Vec<u8> {
+        let factory = class.member_ref(
+            10,
+            "Test",
+            "bootstrap",
+            "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;\
+             Ljava/lang/invoke/MethodType;)Ljava/lang/invoke/CallSite;",
+        );
+        let [high, low] = factory.to_be_bytes();
+        let handle = class.constant(15, &[6, high, low]);
+        let mut info = count.to_be_bytes().to_vec();
+        for _ in 0..count {
+            info.extend_from_slice(&handle.to_be_bytes());
+            info.extend_from_slice(&[0, 0]);
+        }
+        class.attribute("BootstrapMethods", &info)
+    }
+
+    /// The index and error of each violation that parsing `class` reports.
+    fn violations(class: &ClassBuilder) -> Vec<(u16, ConstantPoolError)> {
+        match ClassFile::parse(&class.build()).err() {
+            Some(ClassFormatError::InvalidConstantPool { violations }) => violations
+                .into_iter()
+                .map(|violation| (violation.index, violation.error))
+                .collect(),
+            error => panic!("expected constant pool violations, got {error:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_names_that_are_not_unqualified() {
+        let invalid = |index, name: &str, expected| ConstantPoolError::InvalidName {
+            index,
+            name: name.to_string(),
+            expected,
+        };
+        // Both the NameAndType and the entry using it break the rules.
+        let mut class = ClassBuilder::new(52);
+        let field = class.member_ref(9, "Test", "java/lang/count", "I");
+        let name = class.utf8("java/lang/count");
+        let error = invalid(name, "java/lang/count", "field name");
+        assert_eq!(
+            violations(&class),
+            [(field - 1, error.clone()), (field, error)]
+        );
+
+        for name in ["a.b", "a;", "[a", "a<b", "b>", "<", "<initial>"] {
+            let mut class = ClassBuilder::new(52);
+            let method = class.member_ref(10, "Test", name, "()V");
+            let name_index = class.utf8(name);
+            let error = invalid(name_index, name, "method name");
+            assert_eq!(
+                violations(&class),
+                [(method - 1, error.clone()), (method, error)],
+                "{name}"
+            );
         }
+
+        let mut class = ClassBuilder::new(51);
+        let empty = class.utf8("");
+        let int = class.utf8("I");
+        let name_and_type = class.reference(12, &[empty, int]);
+        assert_eq!(
+            violations(&class),
+            [(name_and_type, invalid(empty, "", "field name"))]
+        );
+    }
+
+    #[test]
+    fn rejects_special_method_names_where_they_are_not_allowed() {
+        let illegal = |index, name: &str| ConstantPoolError::IllegalName {
+            index,
+            name: name.to_string(),
+        };
+        for name in ["<init>", "<clinit>"] {
+            let mut class = ClassBuilder::new(52);
+            let method = class.member_ref(11, "java/util/List", name, "()V");
+            assert_eq!(violations(&class), [(method, illegal(method - 1, name))]);
+
+            let mut class = ClassBuilder::new(52);
+            let name_and_type = class.name_and_type(name, "()V");
+            let invoke_dynamic = class.reference(18, &[0, name_and_type]);
+            assert_eq!(
+                violations(&class),
+                [(invoke_dynamic, illegal(name_and_type, name))]
+            );
+        }
+        let mut class = ClassBuilder::new(52);
+        let method = class.member_ref(10, "Test", "<clinit>", "()V");
+        assert_eq!(
+            violations(&class),
+            [(method, illegal(method - 1, "<clinit>"))]
+        );
+    }
+
+    #[test]
+    fn checks_bootstrap_method_indices() {
+        // The Dynamic entry uses the second bootstrap method, out of `count`
+        // when there is a BootstrapMethods attribute.
+        let class_with = |count: Option<u16>| {
+            let mut class = ClassBuilder::new(55);
+            let name_and_type = class.name_and_type("get", "()I");
+            let invoke_dynamic = class.reference(18, &[0, name_and_type]);
+            let name_and_type = class.name_and_type("value", "I");
+            let dynamic = class.reference(17, &[1, name_and_type]);
+            if let Some(count) = count {
+                let attribute = bootstrap_methods(&mut class, count);
+                class.class_attribute(attribute);
+            }
+            (class, invoke_dynamic, dynamic)
+        };
+        let (class, invoke_dynamic, dynamic) = class_with(None);
+        let missing = |index, kind| ConstantPoolError::MissingBootstrapMethods { index, kind };
+        assert_eq!(
+            violations(&class),
+            [
+                (invoke_dynamic, missing(invoke_dynamic, "InvokeDynamic")),
+                (dynamic, missing(dynamic, "Dynamic")),
+            ]
+        );
+        assert!(ClassFile::parse(&class_with(Some(2)).0.build()).is_ok());
+
+        let (one, _, dynamic) = class_with(Some(1));
+        let error = ClassFile::parse(&one.build()).unwrap_err();
+        assert_eq!(
+            error,
+            ClassFormatError::InvalidConstantPool {
+                violations: vec![ConstantPoolViolation {
+                    index: dynamic,
+                    offset: one.offset_of(dynamic),
+                    error: ConstantPoolError::InvalidBootstrapMethodIndex {
+                        index: dynamic,
+                        bootstrap_method: 1,
+                        count: 1,
+                    },
+                }],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "the constant pool is invalid; entry #{dynamic} at offset {}: constant pool \
+index #{dynamic} refers to bootstrap method 1, past the end of the BootstrapMethods attribute \
+(length 1)",
+                one.offset_of(dynamic)
+            )
+        );
     }
-    return true;
 }
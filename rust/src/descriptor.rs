@@ -0,0 +1,212 @@
+use std::fmt;
+use std::str::Chars;
+
+/// The type of a field, parameter, local variable or value as described by
+/// a field descriptor (JVMS §4.3.2).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FieldDescriptor {
+    Byte,
+    Boolean,
+    Char,
+    Double,
+    Float,
+    Integer,
+    Long,
+    /// A class or interface type, split into the identifiers of its binary
+    /// name.
+    Reference(Vec<String>),
+    Short,
+    /// An array with the given number of dimensions and element type.
+    Array(u8, Box<FieldDescriptor>),
+}
+
+/// The parameter and return types of a method as described by a method
+/// descriptor (JVMS §4.3.3).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MethodDescriptor {
+    pub params: Vec<FieldDescriptor>,
+    /// The return type, or `None` for `void`.
+    pub ret: Option<FieldDescriptor>,
+}
+
+impl FieldDescriptor {
+    /// The number of local variable or operand stack slots a value of the
+    /// type takes, which is two for `long` and `double` and one otherwise.
+    pub fn slots(&self) -> usize {
+        match self {
+            FieldDescriptor::Long | FieldDescriptor::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl MethodDescriptor {
+    /// The most local variable slots the parameters of a method can take,
+    /// counting `this` for instance methods (JVMS §4.3.3).
+    pub const MAX_PARAM_SLOTS: usize = 255;
+
+    /// The number of local variable slots the parameters take, not counting
+    /// `this` for instance methods.
+    pub fn param_slots(&self) -> usize {
+        self.params.iter().map(FieldDescriptor::slots).sum()
+    }
+}
+
+/// Prints the type the way it is written in Java source, such as
+/// `java.lang.String[]`.
+impl fmt::Display for FieldDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldDescriptor::Byte => f.write_str("byte"),
+            FieldDescriptor::Boolean => f.write_str("boolean"),
+            FieldDescriptor::Char => f.write_str("char"),
+            FieldDescriptor::Double => f.write_str("double"),
+            FieldDescriptor::Float => f.write_str("float"),
+            FieldDescriptor::Integer => f.write_str("int"),
+            FieldDescriptor::Long => f.write_str("long"),
+            FieldDescriptor::Reference(identifiers) => f.write_str(&identifiers.join(".")),
+            FieldDescriptor::Short => f.write_str("short"),
+            FieldDescriptor::Array(dimensions, element) => {
+                write!(f, "{element}")?;
+                for _ in 0..*dimensions {
+                    f.write_str("[]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads the identifiers of a class name up to and including the `;` that
+/// ends a reference type.
+fn parse_class_identifiers(chars: &mut Chars<'_>) -> Option<Vec<String>> {
+    let mut temp: Vec<char> = Vec::with_capacity(16);
+    let mut identifiers: Vec<String> = Vec::with_capacity(8);
+    for c in chars.by_ref() {
+        match c {
+            '.' | '[' => {
+                return None;
+            }
+            '/' | ';' => {
+                if temp.is_empty() {
+                    return None;
+                }
+                identifiers.push(temp.iter().collect());
+                temp.clear();
+                if c == ';' {
+                    return Some(identifiers);
+                }
+            }
+            _ => temp.push(c),
+        }
+    }
+    // The reference type was never terminated.
+    None
+}
+
+/// Maps a base type character to its descriptor.
+fn parse_base_type(c: char) -> Option<FieldDescriptor> {
+    match c {
+        'B' => Some(FieldDescriptor::Byte),
+        'C' => Some(FieldDescriptor::Char),
+        'D' => Some(FieldDescriptor::Double),
+        'F' => Some(FieldDescriptor::Float),
+        'I' => Some(FieldDescriptor::Integer),
+        'J' => Some(FieldDescriptor::Long),
+        'S' => Some(FieldDescriptor::Short),
+        'Z' => Some(FieldDescriptor::Boolean),
+        _ => None,
+    }
+}
+
+/// Reads a single field type that starts with the character `first`.
+fn parse_field_type(first: char, chars: &mut Chars<'_>) -> Option<FieldDescriptor> {
+    match first {
+        'L' => Some(FieldDescriptor::Reference(parse_class_identifiers(chars)?)),
+        '[' => {
+            let mut depth: usize = 1;
+            let element = loop {
+                match chars.next()? {
+                    '[' => depth += 1,
+                    'L' => break FieldDescriptor::Reference(parse_class_identifiers(chars)?),
+                    c => break parse_base_type(c)?,
+                }
+            };
+            // An array type can have at most 255 dimensions.
+            if depth > 255 {
+                return None;
+            }
+            Some(FieldDescriptor::Array(depth as u8, Box::new(element)))
+        }
+        c => parse_base_type(c),
+    }
+}
+
+pub fn parse_field_descriptor(descriptor: &str) -> Option<FieldDescriptor> {
+    let mut chars = descriptor.chars();
+    let first = chars.next()?;
+    let result = parse_field_type(first, &mut chars)?;
+    // Anything after the end of the type makes the descriptor invalid.
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(result)
+}
+
+/// Parses a method descriptor, which is only valid when its parameters fit in
+/// [`MethodDescriptor::MAX_PARAM_SLOTS`]. Instance methods have one slot less
+/// to spare, which is left to where the method is known to be one.
+pub fn parse_method_descriptor(descriptor: &str) -> Option<MethodDescriptor> {
+    let mut chars = descriptor.chars();
+    if chars.next()? != '(' {
+        return None;
+    }
+    let mut params: Vec<FieldDescriptor> = Vec::with_capacity(8);
+    loop {
+        match chars.next()? {
+            ')' => break,
+            c => params.push(parse_field_type(c, &mut chars)?),
+        }
+    }
+    let ret = match chars.next()? {
+        'V' => None,
+        c => Some(parse_field_type(c, &mut chars)?),
+    };
+    if chars.next().is_some() {
+        return None;
+    }
+    let descriptor = MethodDescriptor { params, ret };
+    if descriptor.param_slots() > MethodDescriptor::MAX_PARAM_SLOTS {
+        return None;
+    }
+    Some(descriptor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_parameter_slots() {
+        let descriptor = parse_method_descriptor("(Ljava/lang/String;I)V").unwrap();
+        assert_eq!(
+            descriptor.params,
+            [
+                FieldDescriptor::Reference(vec!["java".into(), "lang".into(), "String".into()]),
+                FieldDescriptor::Integer,
+            ]
+        );
+        assert_eq!(descriptor.ret, None);
+        assert_eq!(descriptor.param_slots(), 2);
+        let descriptor = parse_method_descriptor("(JD[JZ)J").unwrap();
+        assert_eq!(descriptor.param_slots(), 6);
+        assert_eq!(descriptor.ret.map(|ret| ret.slots()), Some(2));
+        assert_eq!(parse_method_descriptor("()V").unwrap().param_slots(), 0);
+        assert!(parse_method_descriptor("(I").is_none());
+        // 128 longs take 256 slots, one more than any method may have.
+        let longs = "J".repeat(128);
+        assert!(parse_method_descriptor(&format!("({longs})V")).is_none());
+        let descriptor = parse_method_descriptor(&format!("({}I)V", &longs[1..])).unwrap();
+        assert_eq!(descriptor.param_slots(), 255);
+    }
+}
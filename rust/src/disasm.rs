@@ -0,0 +1,651 @@
+use crate::constantpool::quote;
+use crate::{
+    Attribute, AttributeInfo, CFAccessFlags, ClassFile, Code, ConstantPoolItem, FieldDescriptor,
+    FieldFlag, FieldInfo, InnerClassFlag, Instruction, MethodFlag, MethodInfo, ParameterFlag, Wide,
+};
+
+use std::fmt;
+
+/// The Java modifiers shown in the declaration of a class.
+const CLASS_MODIFIERS: &[(CFAccessFlags, &str)] = &[
+    (CFAccessFlags::Public, "public"),
+    (CFAccessFlags::Final, "final"),
+];
+
+/// The Java modifiers shown in the declaration of a field.
+const FIELD_MODIFIERS: &[(FieldFlag, &str)] = &[
+    (FieldFlag::Public, "public"),
+    (FieldFlag::Private, "private"),
+    (FieldFlag::Protected, "protected"),
+    (FieldFlag::Static, "static"),
+    (FieldFlag::Final, "final"),
+    (FieldFlag::Volatile, "volatile"),
+    (FieldFlag::Transient, "transient"),
+];
+
+/// The Java modifiers shown in the declaration of a method.
+const METHOD_MODIFIERS: &[(MethodFlag, &str)] = &[
+    (MethodFlag::Public, "public"),
+    (MethodFlag::Private, "private"),
+    (MethodFlag::Protected, "protected"),
+    (MethodFlag::Static, "static"),
+    (MethodFlag::Final, "final"),
+    (MethodFlag::Synchronized, "synchronized"),
+    (MethodFlag::Native, "native"),
+    (MethodFlag::Abstract, "abstract"),
+];
+
+/// The Java modifiers shown for an entry of the InnerClasses attribute.
+const INNER_CLASS_MODIFIERS: &[(InnerClassFlag, &str)] = &[
+    (InnerClassFlag::Public, "public"),
+    (InnerClassFlag::Private, "private"),
+    (InnerClassFlag::Protected, "protected"),
+    (InnerClassFlag::Static, "static"),
+    (InnerClassFlag::Final, "final"),
+];
+
+/// The flags shown for an entry of the MethodParameters attribute.
+const PARAMETER_MODIFIERS: &[(ParameterFlag, &str)] = &[
+    (ParameterFlag::Final, "final"),
+    (ParameterFlag::Synthetic, "synthetic"),
+    (ParameterFlag::Mandated, "mandated"),
+];
+
+/// A class file printed in a stable layout close to `javap -c -v`.
+///
+/// Unlike javap the file's modification time and checksum are left out and
+/// generic signatures are not folded into the declarations, so the output
+/// only depends on the contents of the class file.
+pub struct Disassembly<'a> {
+    class_file: &'a ClassFile,
+}
+
+impl ClassFile {
+    /// Returns a value that prints the disassembled class file.
+    pub fn disassemble(&self) -> Disassembly<'_> {
+        Disassembly { class_file: self }
+    }
+}
+
+impl fmt::Display for Disassembly<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class_file = self.class_file;
+        for info in &class_file.attributes {
+            if let Attribute::SourceFile(index) = info.attribute {
+                writeln!(f, "  Compiled from \"{}\"", self.utf8(index))?;
+            }
+        }
+        writeln!(f, "{}", self.class_declaration())?;
+        writeln!(f, "  minor version: {}", class_file.minor_version)?;
+        writeln!(f, "  major version: {}", class_file.major_version)?;
+        writeln!(
+            f,
+            "  flags: {}",
+            flags(class_file.access_flags, class_file.flags().names())
+        )?;
+        writeln!(
+            f,
+            "  {}",
+            commented(
+                &format!("this_class: #{}", class_file.this_class),
+                &self.resolve(class_file.this_class)
+            )
+        )?;
+        if class_file.super_class == 0 {
+            writeln!(f, "  super_class: #0")?;
+        } else {
+            writeln!(
+                f,
+                "  {}",
+                commented(
+                    &format!("super_class: #{}", class_file.super_class),
+                    &self.resolve(class_file.super_class)
+                )
+            )?;
+        }
+        writeln!(
+            f,
+            "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            class_file.interfaces.len(),
+            class_file.fields.len(),
+            class_file.methods.len(),
+            class_file.attributes.len()
+        )?;
+        writeln!(f, "Constant pool:")?;
+        write!(f, "{}", class_file.constant_pool)?;
+        writeln!(f, "{{")?;
+        let mut first = true;
+        for field in &class_file.fields {
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            self.field(f, field)?;
+        }
+        for method in &class_file.methods {
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            self.method(f, method)?;
+        }
+        writeln!(f, "}}")?;
+        for info in &class_file.attributes {
+            self.attribute(f, 0, info)?;
+        }
+        Ok(())
+    }
+}
+
+impl Disassembly<'_> {
+    fn utf8(&self, index: u16) -> String {
+        self.class_file
+            .constant_pool
+            .utf8(index)
+            .map_or_else(|error| format!("<{error}>"), str::to_string)
+    }
+
+    fn resolve(&self, index: u16) -> String {
+        self.class_file
+            .constant_pool
+            .resolve(index)
+            .unwrap_or_else(|error| format!("<{error}>"))
+    }
+
+    /// The binary name of the class at `index`, with dots between packages.
+    fn java_class_name(&self, index: u16) -> String {
+        self.class_file
+            .constant_pool
+            .class_name(index)
+            .map_or_else(|error| format!("<{error}>"), |name| name.replace('/', "."))
+    }
+
+    /// The value the constant at `index` resolves to, prefixed with its kind
+    /// the way javap comments instruction operands.
+    fn constant(&self, index: u16) -> String {
+        let constant_pool = &self.class_file.constant_pool;
+        let item = match constant_pool.get(index) {
+            Ok(item) => item,
+            Err(error) => return format!("<{error}>"),
+        };
+        let kind = match item {
+            ConstantPoolItem::Integer(_) => "int",
+            ConstantPoolItem::Float(_) => "float",
+            ConstantPoolItem::Long(_) => "long",
+            ConstantPoolItem::Double(_) => "double",
+            ConstantPoolItem::Class(_) => "class",
+            ConstantPoolItem::String(_) => "String",
+            ConstantPoolItem::Fieldref(..) => "Field",
+            ConstantPoolItem::Methodref(..) => "Method",
+            ConstantPoolItem::InterfaceMethodref(..) => "InterfaceMethod",
+            ConstantPoolItem::MethodHandle(..) => "MethodHandle",
+            ConstantPoolItem::MethodType(_) => "MethodType",
+            ConstantPoolItem::InvokeDynamic(..) => "InvokeDynamic",
+            ConstantPoolItem::Dynamic(..) => "Dynamic",
+            _ => return self.resolve(index),
+        };
+        // Members of the class itself are shown without the class name.
+        let this_class = constant_pool.class_name(self.class_file.this_class);
+        match constant_pool.member_ref(index) {
+            Ok(member) if this_class == Ok(member.class_name) => {
+                format!("{kind} {}:{}", quote(member.name), member.descriptor)
+            }
+            _ => format!("{kind} {}", self.resolve(index)),
+        }
+    }
+
+    fn class_declaration(&self) -> String {
+        let class_file = self.class_file;
+        let class_flags = class_file.flags();
+        let interface = class_flags.contains(CFAccessFlags::Interface);
+        let mut words = modifiers(class_flags.flags(), CLASS_MODIFIERS);
+        if class_flags.contains(CFAccessFlags::Abstract) && !interface {
+            words.push("abstract".to_string());
+        }
+        words.push(if interface { "interface" } else { "class" }.to_string());
+        words.push(self.java_class_name(class_file.this_class));
+        if class_file.super_class != 0 && !interface {
+            words.push(format!(
+                "extends {}",
+                self.java_class_name(class_file.super_class)
+            ));
+        }
+        if !class_file.interfaces.is_empty() {
+            let names: Vec<String> = class_file
+                .interfaces
+                .iter()
+                .map(|interface| interface.name.replace('/', "."))
+                .collect();
+            let keyword = if interface { "extends" } else { "implements" };
+            words.push(format!("{keyword} {}", names.join(", ")));
+        }
+        words.join(" ")
+    }
+
+    fn field(&self, f: &mut fmt::Formatter<'_>, field: &FieldInfo) -> fmt::Result {
+        let mut words = modifiers(field.flags().flags(), FIELD_MODIFIERS);
+        words.push(field.descriptor.to_string());
+        words.push(field.name.to_str_lossy().to_string());
+        writeln!(f, "  {};", words.join(" "))?;
+        writeln!(f, "    descriptor: {}", self.utf8(field.descriptor_index))?;
+        writeln!(
+            f,
+            "    flags: {}",
+            flags(field.access_flags, field.flags().names())
+        )?;
+        for info in &field.attributes {
+            self.attribute(f, 4, info)?;
+        }
+        Ok(())
+    }
+
+    fn method(&self, f: &mut fmt::Formatter<'_>, method: &MethodInfo) -> fmt::Result {
+        let mut words = modifiers(method.flags().flags(), METHOD_MODIFIERS);
+        let varargs = method.flags().contains(MethodFlag::Varargs);
+        let mut parameters: Vec<String> = method
+            .descriptor
+            .params
+            .iter()
+            .map(FieldDescriptor::to_string)
+            .collect();
+        if varargs {
+            if let Some(last) = parameters.last_mut() {
+                if let Some(element) = last.strip_suffix("[]") {
+                    *last = format!("{element}...");
+                }
+            }
+        }
+        match method.name.to_str_lossy() {
+            "<clinit>" => words.push("{}".to_string()),
+            name => {
+                if name == "<init>" {
+                    words.push(self.java_class_name(self.class_file.this_class));
+                } else {
+                    words.push(match &method.descriptor.ret {
+                        Some(ret) => ret.to_string(),
+                        None => "void".to_string(),
+                    });
+                    words.push(name.to_string());
+                }
+                let last = words.pop().unwrap_or_default();
+                words.push(format!("{last}({})", parameters.join(", ")));
+            }
+        }
+        for info in &method.attributes {
+            if let Attribute::Exceptions(exceptions) = &info.attribute {
+                let names: Vec<String> = exceptions
+                    .iter()
+                    .map(|exception| exception.name.replace('/', "."))
+                    .collect();
+                words.push(format!("throws {}", names.join(", ")));
+            }
+        }
+        writeln!(f, "  {};", words.join(" "))?;
+        writeln!(f, "    descriptor: {}", self.utf8(method.descriptor_index))?;
+        writeln!(
+            f,
+            "    flags: {}",
+            flags(method.access_flags, method.flags().names())
+        )?;
+        for info in &method.attributes {
+            match &info.attribute {
+                Attribute::Code(code) => self.code(f, method, code)?,
+                _ => self.attribute(f, 4, info)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn code(&self, f: &mut fmt::Formatter<'_>, method: &MethodInfo, code: &Code) -> fmt::Result {
+        let mut args_size = method.descriptor.param_slots();
+        if !method.flags().contains(MethodFlag::Static) {
+            args_size += 1;
+        }
+        writeln!(f, "    Code:")?;
+        writeln!(
+            f,
+            "      stack={}, locals={}, args_size={args_size}",
+            code.max_stack, code.max_locals
+        )?;
+        for result in code.instructions(&self.class_file.constant_pool, self.class_file.version()) {
+            match result {
+                Ok((pc, instruction)) => self.instruction(f, pc, &instruction)?,
+                Err(error) => {
+                    writeln!(f, "      <{error}>")?;
+                    break;
+                }
+            }
+        }
+        if !code.exception_table.is_empty() {
+            writeln!(f, "      Exception table:")?;
+            writeln!(f, "         from    to  target type")?;
+            for handler in &code.exception_table {
+                let catch_type = match &handler.catch_type {
+                    Some(class) => format!("Class {}", quote(&class.name)),
+                    None => "any".to_string(),
+                };
+                writeln!(
+                    f,
+                    "      {:>8}{:>6}{:>6}   {catch_type}",
+                    handler.start_pc, handler.end_pc, handler.handler_pc
+                )?;
+            }
+        }
+        for info in &code.attributes {
+            self.attribute(f, 6, info)?;
+        }
+        Ok(())
+    }
+
+    fn instruction(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        pc: u16,
+        instruction: &Instruction,
+    ) -> fmt::Result {
+        let target = |offset: i32| pc as i64 + offset as i64;
+        let mnemonic = instruction.mnemonic();
+        let (operands, comment) = match instruction {
+            Instruction::Bipush(value) => (value.to_string(), None),
+            Instruction::Sipush(value) => (value.to_string(), None),
+            Instruction::Ldc(index) => (format!("#{index}"), Some(self.constant(*index as u16))),
+            Instruction::LdcW(index)
+            | Instruction::Ldc2W(index)
+            | Instruction::Getstatic(index)
+            | Instruction::Putstatic(index)
+            | Instruction::Getfield(index)
+            | Instruction::Putfield(index)
+            | Instruction::Invokevirtual(index)
+            | Instruction::Invokespecial(index)
+            | Instruction::Invokestatic(index)
+            | Instruction::New(index)
+            | Instruction::Anewarray(index)
+            | Instruction::Checkcast(index)
+            | Instruction::Instanceof(index) => (format!("#{index}"), Some(self.constant(*index))),
+            Instruction::Iload(index)
+            | Instruction::Lload(index)
+            | Instruction::Fload(index)
+            | Instruction::Dload(index)
+            | Instruction::Aload(index)
+            | Instruction::Istore(index)
+            | Instruction::Lstore(index)
+            | Instruction::Fstore(index)
+            | Instruction::Dstore(index)
+            | Instruction::Astore(index)
+            | Instruction::Ret(index) => (index.to_string(), None),
+            Instruction::Iinc(index, value) => (format!("{index}, {value}"), None),
+            Instruction::Ifeq(offset)
+            | Instruction::Ifne(offset)
+            | Instruction::Iflt(offset)
+            | Instruction::Ifge(offset)
+            | Instruction::Ifgt(offset)
+            | Instruction::Ifle(offset)
+            | Instruction::IfIcmpeq(offset)
+            | Instruction::IfIcmpne(offset)
+            | Instruction::IfIcmplt(offset)
+            | Instruction::IfIcmpge(offset)
+            | Instruction::IfIcmpgt(offset)
+            | Instruction::IfIcmple(offset)
+            | Instruction::IfAcmpeq(offset)
+            | Instruction::IfAcmpne(offset)
+            | Instruction::Goto(offset)
+            | Instruction::Jsr(offset)
+            | Instruction::Ifnull(offset)
+            | Instruction::Ifnonnull(offset) => (target(*offset as i32).to_string(), None),
+            Instruction::GotoW(offset) | Instruction::JsrW(offset) => {
+                (target(*offset).to_string(), None)
+            }
+            Instruction::Tableswitch {
+                default,
+                low,
+                high,
+                offsets,
+            } => {
+                writeln!(f, "{pc:>10}: {mnemonic:<13} {{ // {low} to {high}")?;
+                for (key, offset) in (*low..=*high).zip(offsets) {
+                    writeln!(f, "{key:>24}: {}", target(*offset))?;
+                }
+                writeln!(f, "{:>24}: {}", "default", target(*default))?;
+                return writeln!(f, "            }}");
+            }
+            Instruction::Lookupswitch { default, pairs } => {
+                writeln!(f, "{pc:>10}: {mnemonic:<13} {{ // {}", pairs.len())?;
+                for (key, offset) in pairs {
+                    writeln!(f, "{key:>24}: {}", target(*offset))?;
+                }
+                writeln!(f, "{:>24}: {}", "default", target(*default))?;
+                return writeln!(f, "            }}");
+            }
+            Instruction::Invokeinterface(index, count) => {
+                (format!("#{index},  {count}"), Some(self.constant(*index)))
+            }
+            Instruction::Invokedynamic(index) => {
+                (format!("#{index},  0"), Some(self.constant(*index)))
+            }
+            Instruction::Newarray(array_type) => {
+                // javap leaves an extra space before the element type.
+                (format!(" {}", array_type.name()), None)
+            }
+            Instruction::Multianewarray(index, dimensions) => (
+                format!("#{index},  {dimensions}"),
+                Some(self.constant(*index)),
+            ),
+            Instruction::Wide(wide) => {
+                let operands = match wide {
+                    Wide::Iinc(index, value) => format!("{index}, {value}"),
+                    Wide::Iload(index)
+                    | Wide::Lload(index)
+                    | Wide::Fload(index)
+                    | Wide::Dload(index)
+                    | Wide::Aload(index)
+                    | Wide::Istore(index)
+                    | Wide::Lstore(index)
+                    | Wide::Fstore(index)
+                    | Wide::Dstore(index)
+                    | Wide::Astore(index)
+                    | Wide::Ret(index) => index.to_string(),
+                };
+                let mnemonic = format!("{}_w", wide.mnemonic());
+                return writeln!(f, "{pc:>10}: {mnemonic:<13} {operands}");
+            }
+            _ => return writeln!(f, "{pc:>10}: {mnemonic}"),
+        };
+        let text = format!("{mnemonic:<13} {operands}");
+        match comment {
+            Some(comment) => writeln!(f, "{pc:>10}: {text:<33} // {comment}"),
+            None => writeln!(f, "{pc:>10}: {text}"),
+        }
+    }
+
+    /// Prints an attribute other than Code, indented by `indent` spaces.
+    fn attribute(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        indent: usize,
+        info: &AttributeInfo,
+    ) -> fmt::Result {
+        let pad = " ".repeat(indent);
+        let name = info.attribute.name();
+        match &info.attribute {
+            Attribute::ConstantValue(index) => {
+                writeln!(f, "{pad}ConstantValue: {}", self.constant(*index))
+            }
+            // Code is printed by the method that owns it.
+            Attribute::Code(_) => Ok(()),
+            Attribute::Exceptions(exceptions) => {
+                let names: Vec<String> = exceptions
+                    .iter()
+                    .map(|exception| exception.name.replace('/', "."))
+                    .collect();
+                writeln!(f, "{pad}Exceptions:")?;
+                writeln!(f, "{pad}  throws {}", names.join(", "))
+            }
+            Attribute::InnerClasses(inner_classes) => {
+                writeln!(f, "{pad}InnerClasses:")?;
+                for inner in inner_classes {
+                    let mut words = modifiers(inner.flags().flags(), INNER_CLASS_MODIFIERS);
+                    let mut text = String::new();
+                    let mut comment = String::new();
+                    if inner.inner_name_index != 0 {
+                        text.push_str(&format!("#{}= ", inner.inner_name_index));
+                        comment.push_str(&format!("{}=", self.utf8(inner.inner_name_index)));
+                    }
+                    text.push_str(&format!("#{}", inner.inner_class.index));
+                    comment.push_str(&format!("class {}", quote(&inner.inner_class.name)));
+                    if inner.outer_class_info_index != 0 {
+                        text.push_str(&format!(" of #{}", inner.outer_class_info_index));
+                        comment.push_str(&format!(
+                            " of class {}",
+                            self.resolve(inner.outer_class_info_index)
+                        ));
+                    }
+                    words.push(format!("{text};"));
+                    writeln!(f, "{pad}  {}", commented(&words.join(" "), &comment))?;
+                }
+                Ok(())
+            }
+            Attribute::EnclosingMethod {
+                class,
+                method_index,
+            } => {
+                let mut comment = quote(&class.name);
+                if *method_index != 0 {
+                    comment.push('.');
+                    comment.push_str(&self.resolve(*method_index));
+                }
+                writeln!(
+                    f,
+                    "{pad}{}",
+                    commented(
+                        &format!("EnclosingMethod: #{}.#{method_index}", class.index),
+                        &comment
+                    )
+                )
+            }
+            Attribute::Synthetic | Attribute::Deprecated => writeln!(f, "{pad}{name}: true"),
+            Attribute::Signature(index) => writeln!(
+                f,
+                "{pad}{}",
+                commented(&format!("Signature: #{index}"), &self.resolve(*index))
+            ),
+            Attribute::SourceFile(index) => {
+                writeln!(f, "{pad}SourceFile: \"{}\"", self.utf8(*index))
+            }
+            Attribute::LineNumberTable(lines) => {
+                writeln!(f, "{pad}LineNumberTable:")?;
+                for line in lines {
+                    writeln!(f, "{pad}  line {}: {}", line.line_number, line.start_pc)?;
+                }
+                Ok(())
+            }
+            Attribute::LocalVariableTable(variables)
+            | Attribute::LocalVariableTypeTable(variables) => {
+                writeln!(f, "{pad}{name}:")?;
+                writeln!(f, "{pad}  Start  Length  Slot  Name   Signature")?;
+                for variable in variables {
+                    writeln!(
+                        f,
+                        "{pad}  {:>5}{:>8}{:>6}{:>6}   {}",
+                        variable.start_pc,
+                        variable.length,
+                        variable.index,
+                        self.utf8(variable.name_index),
+                        self.utf8(variable.descriptor_index)
+                    )?;
+                }
+                Ok(())
+            }
+            Attribute::BootstrapMethods(methods) => {
+                writeln!(f, "{pad}BootstrapMethods:")?;
+                for (number, method) in methods.iter().enumerate() {
+                    writeln!(
+                        f,
+                        "{pad}  {number}: #{} {}",
+                        method.method_ref,
+                        self.resolve(method.method_ref)
+                    )?;
+                    writeln!(f, "{pad}    Method arguments:")?;
+                    for argument in &method.arguments {
+                        writeln!(f, "{pad}      #{argument} {}", self.resolve(*argument))?;
+                    }
+                }
+                Ok(())
+            }
+            Attribute::MethodParameters(parameters) => {
+                writeln!(f, "{pad}MethodParameters:")?;
+                writeln!(f, "{pad}  {:<30} Flags", "Name")?;
+                for parameter in parameters {
+                    let name = match parameter.name_index {
+                        0 => "<no name>".to_string(),
+                        index => self.utf8(index),
+                    };
+                    let flags = modifiers(parameter.flags().flags(), PARAMETER_MODIFIERS);
+                    writeln!(f, "{pad}  {name:<30} {}", flags.join(" "))?;
+                }
+                Ok(())
+            }
+            Attribute::NestHost(class) => {
+                writeln!(f, "{pad}NestHost: class {}", quote(&class.name))
+            }
+            Attribute::NestMembers(classes) | Attribute::PermittedSubclasses(classes) => {
+                writeln!(f, "{pad}{name}:")?;
+                for class in classes {
+                    writeln!(f, "{pad}  {}", quote(&class.name))?;
+                }
+                Ok(())
+            }
+            Attribute::Raw { name, bytes } => {
+                writeln!(f, "{pad}{name}: length = {:#x}", bytes.len())?;
+                for chunk in bytes.chunks(16) {
+                    let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02X}")).collect();
+                    writeln!(f, "{pad}   {}", hex.join(" "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Formats access flags as javap does, such as `(0x0021) ACC_PUBLIC,
+/// ACC_SUPER`.
+fn flags<'a>(access_flags: u16, names: impl Iterator<Item = &'a str>) -> String {
+    let set: Vec<&str> = names.collect();
+    if set.is_empty() {
+        format!("({access_flags:#06x})")
+    } else {
+        format!("({access_flags:#06x}) {}", set.join(", "))
+    }
+}
+
+/// The Java modifiers for the set `flags`, which come in the order of
+/// their bits like the tables of names.
+fn modifiers<F: PartialEq>(flags: impl Iterator<Item = F>, names: &[(F, &str)]) -> Vec<String> {
+    flags
+        .filter_map(|flag| names.iter().find(|(named, _)| *named == flag))
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Appends a javap style comment, lined up in the column javap uses.
+fn commented(text: &str, comment: &str) -> String {
+    format!("{text:<39} // {comment}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/Sample.class");
+
+    /// The expected output was checked against `javap -v -p`. It differs
+    /// where javap prints generic signatures or non-ASCII characters, decodes
+    /// attributes this crate keeps raw, such as StackMapTable, and in the
+    /// extra space javap puts in MethodType comments.
+    const SAMPLE_DISASSEMBLY: &str = include_str!("../benches/data/Sample.disasm");
+
+    #[test]
+    fn disassembles_like_javap() {
+        let class_file = ClassFile::parse(SAMPLE).unwrap();
+        assert_eq!(class_file.disassemble().to_string(), SAMPLE_DISASSEMBLY);
+    }
+}
@@ -0,0 +1,668 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The part of the class file that was being parsed when an error was found.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Structure {
+    Magic,
+    Version,
+    ConstantPool,
+    AccessFlags,
+    ThisClass,
+    SuperClass,
+    Interfaces,
+    Fields,
+    Methods,
+    Attributes,
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Structure::Magic => "magic",
+            Structure::Version => "version",
+            Structure::ConstantPool => "constant pool",
+            Structure::AccessFlags => "access flags",
+            Structure::ThisClass => "this_class",
+            Structure::SuperClass => "super_class",
+            Structure::Interfaces => "interfaces",
+            Structure::Fields => "fields",
+            Structure::Methods => "methods",
+            Structure::Attributes => "attributes",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Everything that can be wrong with a class file.
+///
+/// Every error records the byte offset in the input of the structure that
+/// was rejected and, where one is involved, the constant pool index.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClassFormatError {
+    /// The input does not start with `0xCAFEBABE`.
+    BadMagic,
+    /// The class file version is outside of the supported range.
+    UnsupportedVersion { major: u16, minor: u16 },
+    /// The input ended in the middle of a structure.
+    Truncated {
+        structure: Structure,
+        offset: usize,
+        index: Option<u16>,
+    },
+    /// A constant pool entry has a tag this parser does not know.
+    UnknownConstantTag { offset: usize, index: u16, tag: u8 },
+    /// A constant pool entry has a tag that is not allowed in a class file of
+    /// this major version.
+    ConstantTagNotAllowed {
+        offset: usize,
+        index: u16,
+        tag: u8,
+        major_version: u16,
+    },
+    /// A `CONSTANT_Utf8` entry does not hold valid modified UTF-8. The offset
+    /// is that of the first byte that is not allowed.
+    MalformedUtf8 { offset: usize, index: u16 },
+    /// A constant pool index is 0, past the end of the constant pool or the
+    /// unusable slot after a `Long` or `Double` entry.
+    InvalidConstantIndex {
+        structure: Structure,
+        offset: usize,
+        index: u16,
+    },
+    /// A constant pool index refers to an entry of the wrong kind.
+    UnexpectedConstant {
+        structure: Structure,
+        offset: usize,
+        index: u16,
+        expected: &'static str,
+    },
+    /// A descriptor in the constant pool is not well formed.
+    InvalidDescriptor {
+        structure: Structure,
+        offset: usize,
+        index: u16,
+    },
+    /// One or more constant pool entries break the rules of JVMS §4.4.
+    InvalidConstantPool {
+        violations: Vec<ConstantPoolViolation>,
+    },
+    /// The length of an attribute does not match the contents it was decoded
+    /// into.
+    MalformedAttribute { offset: usize, index: u16 },
+    /// A Code attribute has a code_length outside of 1 to 65535, or an
+    /// exception handler with a range or handler that is not in the code.
+    InvalidCode { offset: usize },
+    /// There are bytes left over after the end of the class file.
+    TrailingBytes { offset: usize },
+}
+
+impl ClassFormatError {
+    /// The structure that was being parsed.
+    pub fn structure(&self) -> Structure {
+        match self {
+            ClassFormatError::BadMagic => Structure::Magic,
+            ClassFormatError::UnsupportedVersion { .. } => Structure::Version,
+            ClassFormatError::Truncated { structure, .. }
+            | ClassFormatError::InvalidConstantIndex { structure, .. }
+            | ClassFormatError::UnexpectedConstant { structure, .. }
+            | ClassFormatError::InvalidDescriptor { structure, .. } => *structure,
+            ClassFormatError::UnknownConstantTag { .. }
+            | ClassFormatError::ConstantTagNotAllowed { .. }
+            | ClassFormatError::MalformedUtf8 { .. }
+            | ClassFormatError::InvalidConstantPool { .. } => Structure::ConstantPool,
+            ClassFormatError::MalformedAttribute { .. }
+            | ClassFormatError::InvalidCode { .. }
+            | ClassFormatError::TrailingBytes { .. } => Structure::Attributes,
+        }
+    }
+
+    /// The byte offset in the input where the rejected structure starts.
+    pub fn offset(&self) -> usize {
+        match self {
+            ClassFormatError::BadMagic => 0,
+            ClassFormatError::UnsupportedVersion { .. } => 4,
+            ClassFormatError::Truncated { offset, .. }
+            | ClassFormatError::UnknownConstantTag { offset, .. }
+            | ClassFormatError::ConstantTagNotAllowed { offset, .. }
+            | ClassFormatError::MalformedUtf8 { offset, .. }
+            | ClassFormatError::InvalidConstantIndex { offset, .. }
+            | ClassFormatError::UnexpectedConstant { offset, .. }
+            | ClassFormatError::InvalidDescriptor { offset, .. }
+            | ClassFormatError::MalformedAttribute { offset, .. }
+            | ClassFormatError::InvalidCode { offset }
+            | ClassFormatError::TrailingBytes { offset } => *offset,
+            ClassFormatError::InvalidConstantPool { violations } => {
+                violations.first().map_or(0, |violation| violation.offset)
+            }
+        }
+    }
+
+    /// The constant pool index involved in the error, if there is one.
+    pub fn index(&self) -> Option<u16> {
+        match self {
+            ClassFormatError::Truncated { index, .. } => *index,
+            ClassFormatError::UnknownConstantTag { index, .. }
+            | ClassFormatError::ConstantTagNotAllowed { index, .. }
+            | ClassFormatError::MalformedUtf8 { index, .. }
+            | ClassFormatError::InvalidConstantIndex { index, .. }
+            | ClassFormatError::UnexpectedConstant { index, .. }
+            | ClassFormatError::InvalidDescriptor { index, .. }
+            | ClassFormatError::MalformedAttribute { index, .. } => Some(*index),
+            ClassFormatError::InvalidConstantPool { violations } => {
+                violations.first().map(|violation| violation.index)
+            }
+            ClassFormatError::BadMagic
+            | ClassFormatError::UnsupportedVersion { .. }
+            | ClassFormatError::InvalidCode { .. }
+            | ClassFormatError::TrailingBytes { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ClassFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassFormatError::BadMagic => write!(f, "invalid magic value"),
+            ClassFormatError::UnsupportedVersion { major, minor } => {
+                write!(f, "class file version {major}.{minor} is not supported")
+            }
+            ClassFormatError::Truncated {
+                structure,
+                offset,
+                index: Some(index),
+            } => write!(
+                f,
+                "{structure} entry #{index} at offset {offset} is truncated"
+            ),
+            ClassFormatError::Truncated {
+                structure, offset, ..
+            } => write!(f, "{structure} at offset {offset} is truncated"),
+            ClassFormatError::UnknownConstantTag { offset, index, tag } => write!(
+                f,
+                "constant pool entry #{index} at offset {offset} has unknown tag {tag}"
+            ),
+            ClassFormatError::ConstantTagNotAllowed {
+                offset,
+                index,
+                tag,
+                major_version,
+            } => write!(
+                f,
+                "constant pool entry #{index} at offset {offset} has tag {tag}, which is not \
+allowed in class file version {major_version}"
+            ),
+            ClassFormatError::MalformedUtf8 { offset, index } => write!(
+                f,
+                "constant pool entry #{index} is not valid modified UTF-8 at offset {offset}"
+            ),
+            ClassFormatError::InvalidConstantIndex {
+                structure,
+                offset,
+                index,
+            } => write!(
+                f,
+                "{structure} at offset {offset} refers to invalid constant pool index #{index}"
+            ),
+            ClassFormatError::UnexpectedConstant {
+                structure,
+                offset,
+                index,
+                expected,
+            } => write!(
+                f,
+                "{structure} at offset {offset} expected a {expected} at constant pool index #{index}"
+            ),
+            ClassFormatError::InvalidDescriptor {
+                structure,
+                offset,
+                index,
+            } => write!(
+                f,
+                "{structure} at offset {offset} uses the invalid descriptor at constant pool \
+index #{index}"
+            ),
+            ClassFormatError::InvalidConstantPool { violations } => {
+                write!(f, "the constant pool is invalid")?;
+                for violation in violations {
+                    write!(f, "; {violation}")?;
+                }
+                Ok(())
+            }
+            ClassFormatError::MalformedAttribute { offset, index } => write!(
+                f,
+                "the attribute named by constant pool index #{index} at offset {offset} does not \
+match its length"
+            ),
+            ClassFormatError::InvalidCode { offset } => {
+                write!(f, "the code attribute at offset {offset} is invalid")
+            }
+            ClassFormatError::TrailingBytes { offset } => {
+                write!(f, "unexpected data after the class file at offset {offset}")
+            }
+        }
+    }
+}
+
+impl Error for ClassFormatError {}
+
+/// A failed lookup in a [`crate::ConstantPool`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConstantPoolError {
+    /// The index is 0, past the end of the constant pool or the unusable
+    /// slot after a `Long` or `Double` entry.
+    InvalidIndex(u16),
+    /// The entry at the index is not of the expected kind.
+    UnexpectedType { index: u16, expected: &'static str },
+    /// The `CONSTANT_MethodHandle` at the index has a reference_kind outside
+    /// of 1 to 9.
+    InvalidReferenceKind { index: u16, kind: u8 },
+    /// The member referred to at the index has a name that is not allowed
+    /// there.
+    IllegalName { index: u16, name: String },
+    /// The `CONSTANT_Utf8` at the index does not hold the kind of descriptor
+    /// that is expected, such as a `field descriptor`.
+    InvalidDescriptor {
+        index: u16,
+        descriptor: String,
+        expected: &'static str,
+    },
+    /// The `CONSTANT_Utf8` at the index is used as the name of a class but
+    /// is neither a binary name in internal form nor an array descriptor.
+    InvalidClassName { index: u16, name: String },
+    /// The `CONSTANT_Utf8` at the index is used as the name of a field or
+    /// method but is not an unqualified name (JVMS §4.2.2), such as a
+    /// `method name`.
+    InvalidName {
+        index: u16,
+        name: String,
+        expected: &'static str,
+    },
+    /// The `CONSTANT_Module` or `CONSTANT_Package` at the index is in a
+    /// class file that does not have `ACC_MODULE` set.
+    OutsideOfModule { index: u16, kind: &'static str },
+    /// The `CONSTANT_InvokeDynamic` or `CONSTANT_Dynamic` at the index is in
+    /// a class file without a BootstrapMethods attribute.
+    MissingBootstrapMethods { index: u16, kind: &'static str },
+    /// The `CONSTANT_InvokeDynamic` or `CONSTANT_Dynamic` at the index refers
+    /// to an entry past the end of the BootstrapMethods attribute, which has
+    /// `count` entries.
+    InvalidBootstrapMethodIndex {
+        index: u16,
+        bootstrap_method: u16,
+        count: usize,
+    },
+}
+
+impl ConstantPoolError {
+    /// Turns the failed lookup into a [`ClassFormatError`] for the structure
+    /// at `offset` that made it.
+    pub fn at(self, structure: Structure, offset: usize) -> ClassFormatError {
+        match self {
+            ConstantPoolError::InvalidIndex(index) => ClassFormatError::InvalidConstantIndex {
+                structure,
+                offset,
+                index,
+            },
+            ConstantPoolError::UnexpectedType { index, expected } => {
+                ClassFormatError::UnexpectedConstant {
+                    structure,
+                    offset,
+                    index,
+                    expected,
+                }
+            }
+            error @ (ConstantPoolError::InvalidReferenceKind { index, .. }
+            | ConstantPoolError::IllegalName { index, .. }
+            | ConstantPoolError::InvalidDescriptor { index, .. }
+            | ConstantPoolError::InvalidClassName { index, .. }
+            | ConstantPoolError::InvalidName { index, .. }
+            | ConstantPoolError::OutsideOfModule { index, .. }
+            | ConstantPoolError::MissingBootstrapMethods { index, .. }
+            | ConstantPoolError::InvalidBootstrapMethodIndex { index, .. }) => {
+                ClassFormatError::InvalidConstantPool {
+                    violations: vec![ConstantPoolViolation {
+                        index,
+                        offset,
+                        error,
+                    }],
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConstantPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantPoolError::InvalidIndex(index) => {
+                write!(f, "invalid constant pool index #{index}")
+            }
+            ConstantPoolError::UnexpectedType { index, expected } => {
+                write!(f, "expected a {expected} at constant pool index #{index}")
+            }
+            ConstantPoolError::InvalidReferenceKind { index, kind } => write!(
+                f,
+                "the method handle at constant pool index #{index} has invalid reference kind {kind}"
+            ),
+            ConstantPoolError::IllegalName { index, name } => {
+                write!(
+                    f,
+                    "the member at constant pool index #{index} may not be named {name}"
+                )
+            }
+            ConstantPoolError::InvalidDescriptor {
+                index,
+                descriptor,
+                expected,
+            } => write!(
+                f,
+                "constant pool index #{index} holds {descriptor}, which is not a valid {expected}"
+            ),
+            ConstantPoolError::InvalidClassName { index, name } => write!(
+                f,
+                "constant pool index #{index} holds {name}, which is not a valid class name"
+            ),
+            ConstantPoolError::InvalidName {
+                index,
+                name,
+                expected,
+            } => write!(
+                f,
+                "constant pool index #{index} holds {name}, which is not a valid {expected}"
+            ),
+            ConstantPoolError::OutsideOfModule { index, kind } => write!(
+                f,
+                "the {kind} at constant pool index #{index} is only allowed in a module-info \
+class"
+            ),
+            ConstantPoolError::MissingBootstrapMethods { index, kind } => write!(
+                f,
+                "the {kind} at constant pool index #{index} needs a BootstrapMethods attribute"
+            ),
+            ConstantPoolError::InvalidBootstrapMethodIndex {
+                index,
+                bootstrap_method,
+                count,
+            } => write!(
+                f,
+                "constant pool index #{index} refers to bootstrap method {bootstrap_method}, past \
+the end of the BootstrapMethods attribute (length {count})"
+            ),
+        }
+    }
+}
+
+impl Error for ConstantPoolError {}
+
+/// A constant pool entry that breaks the rules of JVMS §4.4.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConstantPoolViolation {
+    /// The index of the offending entry.
+    pub index: u16,
+    /// The byte offset of the offending entry in the class file.
+    pub offset: usize,
+    pub error: ConstantPoolError,
+}
+
+impl fmt::Display for ConstantPoolViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "entry #{} at offset {}: {}",
+            self.index, self.offset, self.error
+        )
+    }
+}
+
+/// Bytecode that could not be decoded into instructions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BytecodeError {
+    /// The byte at the pc is not an opcode that may appear in a class file.
+    UnknownOpcode { pc: u16, opcode: u8 },
+    /// The code ends in the middle of the instruction at the pc.
+    Truncated { pc: u16 },
+    /// The instruction at the pc has an operand outside of its allowed
+    /// values, such as an unsorted `lookupswitch` or a `wide` prefix on an
+    /// instruction that cannot be widened.
+    InvalidOperand { pc: u16 },
+    /// The instruction at the pc refers to a missing or unsuitable constant
+    /// pool entry.
+    Constant { pc: u16, error: ConstantPoolError },
+}
+
+impl BytecodeError {
+    /// The pc of the instruction that could not be decoded.
+    pub fn pc(&self) -> u16 {
+        match self {
+            BytecodeError::UnknownOpcode { pc, .. }
+            | BytecodeError::Truncated { pc }
+            | BytecodeError::InvalidOperand { pc }
+            | BytecodeError::Constant { pc, .. } => *pc,
+        }
+    }
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytecodeError::UnknownOpcode { pc, opcode } => {
+                write!(f, "unknown opcode {opcode:#04x} at pc {pc}")
+            }
+            BytecodeError::Truncated { pc } => {
+                write!(f, "the instruction at pc {pc} is truncated")
+            }
+            BytecodeError::InvalidOperand { pc } => {
+                write!(f, "the instruction at pc {pc} has an invalid operand")
+            }
+            BytecodeError::Constant { pc, error } => {
+                write!(f, "the instruction at pc {pc} is invalid: {error}")
+            }
+        }
+    }
+}
+
+impl Error for BytecodeError {}
+
+/// A class file that could not be read from a [`std::io::Read`] source.
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading from the source failed.
+    Io(io::Error),
+    /// The source holds more bytes than the limit allows.
+    TooLarge { max_size: u64 },
+    /// The bytes that were read are not a valid class file.
+    Format(ClassFormatError),
+}
+
+impl From<io::Error> for ReadError {
+    fn from(error: io::Error) -> Self {
+        ReadError::Io(error)
+    }
+}
+
+impl From<ClassFormatError> for ReadError {
+    fn from(error: ClassFormatError) -> Self {
+        ReadError::Format(error)
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(error) => write!(f, "{error}"),
+            ReadError::TooLarge { max_size } => {
+                write!(f, "the class file is larger than {max_size} bytes")
+            }
+            ReadError::Format(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadError::Io(error) => Some(error),
+            ReadError::TooLarge { .. } => None,
+            ReadError::Format(error) => Some(error),
+        }
+    }
+}
+
+/// A combination of access flags the specification rules out, with the flags
+/// named as in javap.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AccessFlagsViolation {
+    /// `flag` is set without `required`.
+    Requires {
+        flag: &'static str,
+        required: &'static str,
+    },
+    /// `flag` and `other` are both set.
+    Conflicts {
+        flag: &'static str,
+        other: &'static str,
+    },
+    /// `required` is not set, although every `context`, such as `an interface
+    /// field`, has it.
+    Missing {
+        required: &'static str,
+        context: &'static str,
+    },
+    /// `flag` is set, although no `context` may have it.
+    NotAllowed {
+        flag: &'static str,
+        context: &'static str,
+    },
+}
+
+impl fmt::Display for AccessFlagsViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessFlagsViolation::Requires { flag, required } => {
+                write!(f, "{flag} requires {required}")
+            }
+            AccessFlagsViolation::Conflicts { flag, other } => {
+                write!(f, "{flag} can not be combined with {other}")
+            }
+            AccessFlagsViolation::Missing { required, context } => {
+                write!(f, "{context} requires {required}")
+            }
+            AccessFlagsViolation::NotAllowed { flag, context } => {
+                write!(f, "{flag} is not allowed on {context}")
+            }
+        }
+    }
+}
+
+impl Error for AccessFlagsViolation {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClassFile;
+    use crate::testing::ClassBuilder;
+
+    fn parse_error(input: &[u8]) -> ClassFormatError {
+        ClassFile::parse(input).unwrap_err()
+    }
+
+    #[test]
+    fn reports_a_bad_magic_at_the_start() {
+        let mut input = ClassBuilder::new(52).build();
+        input[3] = 0xBF;
+        let error = parse_error(&input);
+        assert_eq!(error, ClassFormatError::BadMagic);
+        assert_eq!((error.structure(), error.offset()), (Structure::Magic, 0));
+        assert_eq!(parse_error(b"\xCA\xFE"), ClassFormatError::BadMagic);
+    }
+
+    #[test]
+    fn reports_where_the_input_ends() {
+        let input = ClassBuilder::new(52).build();
+        assert_eq!(
+            parse_error(&input[..6]),
+            ClassFormatError::Truncated {
+                structure: Structure::Version,
+                offset: 6,
+                index: None,
+            }
+        );
+        assert_eq!(
+            parse_error(&input[..9]),
+            ClassFormatError::Truncated {
+                structure: Structure::ConstantPool,
+                offset: 8,
+                index: None,
+            }
+        );
+    }
+
+    #[test]
+    fn reports_a_constant_pool_cut_off_mid_entry() {
+        let mut class = ClassBuilder::new(52);
+        let index = class.long(1);
+        let offset = class.offset_of(index);
+        let input = class.build();
+        let error = parse_error(&input[..offset + 5]);
+        assert_eq!(
+            error,
+            ClassFormatError::Truncated {
+                structure: Structure::ConstantPool,
+                offset,
+                index: Some(index),
+            }
+        );
+        assert_eq!((error.offset(), error.index()), (offset, Some(index)));
+        assert_eq!(
+            error.to_string(),
+            format!("constant pool entry #{index} at offset {offset} is truncated")
+        );
+        // The length of a Utf8 entry is part of the entry too.
+        let mut class = ClassBuilder::new(52);
+        let index = class.utf8("name");
+        let offset = class.offset_of(index);
+        assert_eq!(
+            parse_error(&class.build()[..offset + 2]),
+            ClassFormatError::Truncated {
+                structure: Structure::ConstantPool,
+                offset,
+                index: Some(index),
+            }
+        );
+    }
+
+    #[test]
+    fn reports_the_offending_constant() {
+        let mut class = ClassBuilder::new(52);
+        let index = class.utf8_bytes(b"ab\xFFc");
+        // The tag and the length come before the bytes.
+        let offset = class.offset_of(index) + 3 + 2;
+        let error = parse_error(&class.build());
+        assert_eq!(error, ClassFormatError::MalformedUtf8 { offset, index });
+        assert_eq!(error.index(), Some(index));
+
+        let mut class = ClassBuilder::new(52);
+        let index = class.constant(2, &[]);
+        let offset = class.offset_of(index);
+        assert_eq!(
+            parse_error(&class.build()),
+            ClassFormatError::UnknownConstantTag {
+                offset,
+                index,
+                tag: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn reports_bytes_after_the_class_file() {
+        let mut input = ClassBuilder::new(52).build();
+        let offset = input.len();
+        input.push(0);
+        let error = parse_error(&input);
+        assert_eq!(error, ClassFormatError::TrailingBytes { offset });
+        assert_eq!(error.index(), None);
+    }
+}
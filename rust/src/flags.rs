@@ -0,0 +1,692 @@
+use crate::ClassFileVersion;
+use crate::error::AccessFlagsViolation;
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// Declares the flags of one kind of access_flags item as an enum, along
+/// with a set of them that keeps every bit it was created from.
+macro_rules! access_flags {
+    (
+        $(#[$flag_meta:meta])*
+        flag $flag:ident;
+        $(#[$set_meta:meta])*
+        set $set:ident;
+        $($variant:ident = $bit:literal, $name:literal;)*
+    ) => {
+        $(#[$flag_meta])*
+        #[repr(u16)]
+        #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub enum $flag {
+            $($variant = $bit,)*
+        }
+
+        impl $flag {
+            /// Every flag, in the order of their bits.
+            pub const ALL: &'static [$flag] = &[$($flag::$variant,)*];
+
+            /// The bit of the flag.
+            pub const fn bit(self) -> u16 {
+                self as u16
+            }
+
+            /// The name of the flag in the specification and in javap, such
+            /// as `ACC_PUBLIC`.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $($flag::$variant => $name,)*
+                }
+            }
+        }
+
+        $(#[$set_meta])*
+        ///
+        /// The set keeps bits that are not flags, so converting it back to a
+        /// `u16` gives the value it was created from.
+        #[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+        pub struct $set(u16);
+
+        impl $set {
+            pub const fn from_bits(bits: u16) -> $set {
+                $set(bits)
+            }
+
+            /// Every bit of the set, including those that are not flags.
+            pub const fn bits(self) -> u16 {
+                self.0
+            }
+
+            pub const fn contains(self, flag: $flag) -> bool {
+                self.0 & flag.bit() != 0
+            }
+
+            pub fn insert(&mut self, flag: $flag) {
+                self.0 |= flag.bit();
+            }
+
+            pub fn remove(&mut self, flag: $flag) {
+                self.0 &= !flag.bit();
+            }
+
+            /// The flags that are set, in the order of their bits.
+            pub fn flags(self) -> impl Iterator<Item = $flag> {
+                $flag::ALL
+                    .iter()
+                    .copied()
+                    .filter(move |flag| self.contains(*flag))
+            }
+
+            /// The names of the flags that are set, in the order of their
+            /// bits.
+            pub fn names(self) -> impl Iterator<Item = &'static str> {
+                self.flags().map($flag::name)
+            }
+
+            /// The bits that are set but are not flags.
+            pub const fn unknown_bits(self) -> u16 {
+                self.0 & !($($bit)|*)
+            }
+
+            /// Records a violation when `flag` is set without `required`.
+            fn requires(
+                self,
+                flag: $flag,
+                required: $flag,
+                violations: &mut Vec<AccessFlagsViolation>,
+            ) {
+                if self.contains(flag) && !self.contains(required) {
+                    violations.push(AccessFlagsViolation::Requires {
+                        flag: flag.name(),
+                        required: required.name(),
+                    });
+                }
+            }
+
+            /// Records a violation when both `flag` and `other` are set.
+            fn conflicts(
+                self,
+                flag: $flag,
+                other: $flag,
+                violations: &mut Vec<AccessFlagsViolation>,
+            ) {
+                if self.contains(flag) && self.contains(other) {
+                    violations.push(AccessFlagsViolation::Conflicts {
+                        flag: flag.name(),
+                        other: other.name(),
+                    });
+                }
+            }
+
+            /// Records a violation for each pair of `flags` that are both
+            /// set.
+            fn exclusive(self, flags: &[$flag], violations: &mut Vec<AccessFlagsViolation>) {
+                for (position, &flag) in flags.iter().enumerate() {
+                    for &other in &flags[position + 1..] {
+                        self.conflicts(flag, other, violations);
+                    }
+                }
+            }
+
+            /// Records a violation when `required` is not set, although
+            /// every `context` has it.
+            fn missing(
+                self,
+                required: $flag,
+                context: &'static str,
+                violations: &mut Vec<AccessFlagsViolation>,
+            ) {
+                if !self.contains(required) {
+                    violations.push(AccessFlagsViolation::Missing {
+                        required: required.name(),
+                        context,
+                    });
+                }
+            }
+
+            /// Records a violation for each of `flags` that is set, although
+            /// no `context` may have it.
+            fn not_allowed(
+                self,
+                flags: &[$flag],
+                context: &'static str,
+                violations: &mut Vec<AccessFlagsViolation>,
+            ) {
+                for &flag in flags.iter().filter(|&&flag| self.contains(flag)) {
+                    violations.push(AccessFlagsViolation::NotAllowed {
+                        flag: flag.name(),
+                        context,
+                    });
+                }
+            }
+        }
+
+        impl From<u16> for $set {
+            fn from(bits: u16) -> Self {
+                $set(bits)
+            }
+        }
+
+        impl From<$set> for u16 {
+            fn from(set: $set) -> Self {
+                set.0
+            }
+        }
+
+        impl FromIterator<$flag> for $set {
+            fn from_iter<I: IntoIterator<Item = $flag>>(flags: I) -> Self {
+                $set(flags.into_iter().fold(0, |bits, flag| bits | flag.bit()))
+            }
+        }
+
+        /// Shows the names of the flags and any unknown bits, such as
+        /// `ACC_PUBLIC | ACC_SUPER | 0x0100`.
+        impl fmt::Debug for $set {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut names: Vec<String> =
+                    self.flags().map(|flag| flag.name().to_string()).collect();
+                if self.unknown_bits() != 0 {
+                    names.push(format!("{:#06x}", self.unknown_bits()));
+                }
+                write!(f, "{}({})", stringify!($set), names.join(" | "))
+            }
+        }
+    };
+}
+
+access_flags! {
+    /// A flag of the access_flags item of a class file (JVMS table 4.1-B).
+    flag CFAccessFlags;
+    /// The access_flags item of a class file.
+    set ClassAccessFlags;
+    Public = 0x0001, "ACC_PUBLIC";
+    Final = 0x0010, "ACC_FINAL";
+    Super = 0x0020, "ACC_SUPER";
+    Interface = 0x0200, "ACC_INTERFACE";
+    Abstract = 0x0400, "ACC_ABSTRACT";
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+    Annotation = 0x2000, "ACC_ANNOTATION";
+    Enum = 0x4000, "ACC_ENUM";
+    Module = 0x8000, "ACC_MODULE";
+}
+
+impl ClassAccessFlags {
+    /// Checks the combinations of flags JVMS §4.1 rules out in a class file
+    /// of `version`. Flags that version does not define are ignored, as the
+    /// specification asks of unassigned bits.
+    pub fn validate(self, version: ClassFileVersion) -> Result<(), Vec<AccessFlagsViolation>> {
+        use CFAccessFlags::*;
+        let flags = ClassAccessFlags(self.0 & version.class_access_flags());
+        let mut violations = Vec::new();
+        // A module-info class has no other flag.
+        for &flag in CFAccessFlags::ALL.iter().filter(|&&flag| flag != Module) {
+            flags.conflicts(Module, flag, &mut violations);
+        }
+        flags.requires(Interface, Abstract, &mut violations);
+        for flag in [Final, Super, Enum] {
+            flags.conflicts(Interface, flag, &mut violations);
+        }
+        flags.requires(Annotation, Interface, &mut violations);
+        flags.conflicts(Final, Abstract, &mut violations);
+        checked(violations)
+    }
+}
+
+access_flags! {
+    /// A flag of the access_flags item of a field (JVMS table 4.5-A).
+    flag FieldFlag;
+    /// The access_flags item of a field.
+    set FieldAccessFlags;
+    Public = 0x0001, "ACC_PUBLIC";
+    Private = 0x0002, "ACC_PRIVATE";
+    Protected = 0x0004, "ACC_PROTECTED";
+    Static = 0x0008, "ACC_STATIC";
+    Final = 0x0010, "ACC_FINAL";
+    Volatile = 0x0040, "ACC_VOLATILE";
+    Transient = 0x0080, "ACC_TRANSIENT";
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+    Enum = 0x4000, "ACC_ENUM";
+}
+
+impl FieldAccessFlags {
+    /// Checks the combinations of flags JVMS §4.5 rules out for a field of
+    /// a class, or of an interface when `in_interface` is set, in a class
+    /// file of `version`. Flags that version does not define are ignored.
+    pub fn validate(
+        self,
+        in_interface: bool,
+        version: ClassFileVersion,
+    ) -> Result<(), Vec<AccessFlagsViolation>> {
+        use FieldFlag::*;
+        let flags = FieldAccessFlags(self.0 & version.field_access_flags());
+        let mut violations = Vec::new();
+        if in_interface {
+            for flag in [Public, Static, Final] {
+                flags.missing(flag, "an interface field", &mut violations);
+            }
+            flags.not_allowed(
+                &[Private, Protected, Volatile, Transient, Enum],
+                "an interface field",
+                &mut violations,
+            );
+        } else {
+            flags.exclusive(&[Public, Private, Protected], &mut violations);
+            flags.conflicts(Final, Volatile, &mut violations);
+        }
+        checked(violations)
+    }
+}
+
+access_flags! {
+    /// A flag of the access_flags item of a method (JVMS table 4.6-A).
+    flag MethodFlag;
+    /// The access_flags item of a method.
+    set MethodAccessFlags;
+    Public = 0x0001, "ACC_PUBLIC";
+    Private = 0x0002, "ACC_PRIVATE";
+    Protected = 0x0004, "ACC_PROTECTED";
+    Static = 0x0008, "ACC_STATIC";
+    Final = 0x0010, "ACC_FINAL";
+    Synchronized = 0x0020, "ACC_SYNCHRONIZED";
+    Bridge = 0x0040, "ACC_BRIDGE";
+    Varargs = 0x0080, "ACC_VARARGS";
+    Native = 0x0100, "ACC_NATIVE";
+    Abstract = 0x0400, "ACC_ABSTRACT";
+    Strict = 0x0800, "ACC_STRICT";
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+}
+
+impl MethodAccessFlags {
+    /// Checks the combinations of flags JVMS §4.6 rules out for the method
+    /// called `name` of a class, or of an interface when `in_interface` is
+    /// set, in a class file of `version`. Flags that version does not define
+    /// are ignored, so `ACC_STRICT` only counts from Java 1.2 to 16.
+    pub fn validate(
+        self,
+        name: &str,
+        in_interface: bool,
+        version: ClassFileVersion,
+    ) -> Result<(), Vec<AccessFlagsViolation>> {
+        use MethodFlag::*;
+        let flags = MethodAccessFlags(self.0 & version.method_access_flags());
+        let mut violations = Vec::new();
+        if name == "<clinit>" {
+            // The other flags of a class initialization method are ignored.
+            if version.major >= 51 {
+                flags.missing(Static, "a class initialization method", &mut violations);
+            }
+            return checked(violations);
+        }
+        flags.exclusive(&[Public, Private, Protected], &mut violations);
+        if name == "<init>" {
+            let context = "an instance initialization method";
+            flags.not_allowed(
+                &[Static, Final, Synchronized, Bridge, Native, Abstract],
+                context,
+                &mut violations,
+            );
+        } else if in_interface && version.major < 52 {
+            let context = "an interface method";
+            flags.missing(Public, context, &mut violations);
+            flags.missing(Abstract, context, &mut violations);
+            flags.not_allowed(
+                &[
+                    Private,
+                    Protected,
+                    Static,
+                    Final,
+                    Synchronized,
+                    Native,
+                    Strict,
+                ],
+                context,
+                &mut violations,
+            );
+        } else if in_interface {
+            let context = "an interface method";
+            if !flags.contains(Public) && !flags.contains(Private) {
+                flags.missing(Public, context, &mut violations);
+            }
+            flags.not_allowed(
+                &[Protected, Final, Synchronized, Native],
+                context,
+                &mut violations,
+            );
+        }
+        for flag in [Private, Static, Final, Synchronized, Native, Strict] {
+            flags.conflicts(Abstract, flag, &mut violations);
+        }
+        checked(violations)
+    }
+}
+
+access_flags! {
+    /// A flag of the inner_class_access_flags item of an InnerClasses entry
+    /// (JVMS table 4.7.6-A).
+    flag InnerClassFlag;
+    /// The inner_class_access_flags item of an InnerClasses entry.
+    set InnerClassAccessFlags;
+    Public = 0x0001, "ACC_PUBLIC";
+    Private = 0x0002, "ACC_PRIVATE";
+    Protected = 0x0004, "ACC_PROTECTED";
+    Static = 0x0008, "ACC_STATIC";
+    Final = 0x0010, "ACC_FINAL";
+    Interface = 0x0200, "ACC_INTERFACE";
+    Abstract = 0x0400, "ACC_ABSTRACT";
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+    Annotation = 0x2000, "ACC_ANNOTATION";
+    Enum = 0x4000, "ACC_ENUM";
+}
+
+impl InnerClassAccessFlags {
+    /// Checks the combinations of flags that JVMS §4.1 rules out for a
+    /// class and that a nested class can not have either.
+    pub fn validate(self) -> Result<(), Vec<AccessFlagsViolation>> {
+        use InnerClassFlag::*;
+        let mut violations = Vec::new();
+        self.exclusive(&[Public, Private, Protected], &mut violations);
+        self.requires(Interface, Abstract, &mut violations);
+        for flag in [Final, Enum] {
+            self.conflicts(Interface, flag, &mut violations);
+        }
+        self.requires(Annotation, Interface, &mut violations);
+        self.conflicts(Final, Abstract, &mut violations);
+        checked(violations)
+    }
+}
+
+access_flags! {
+    /// A flag of the access_flags item of a MethodParameters entry (JVMS
+    /// §4.7.24).
+    flag ParameterFlag;
+    /// The access_flags item of a MethodParameters entry.
+    set ParameterAccessFlags;
+    Final = 0x0010, "ACC_FINAL";
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+    Mandated = 0x8000, "ACC_MANDATED";
+}
+
+impl ParameterAccessFlags {
+    /// Checks that the parameter is not both synthetic, that is missing
+    /// from the source, and mandated, that is implicitly declared by it.
+    pub fn validate(self) -> Result<(), Vec<AccessFlagsViolation>> {
+        let mut violations = Vec::new();
+        self.conflicts(
+            ParameterFlag::Synthetic,
+            ParameterFlag::Mandated,
+            &mut violations,
+        );
+        checked(violations)
+    }
+}
+
+access_flags! {
+    /// A flag of the module_flags item of the Module attribute (JVMS
+    /// §4.7.25).
+    flag ModuleFlag;
+    /// The module_flags item of the Module attribute.
+    set ModuleAccessFlags;
+    Open = 0x0020, "ACC_OPEN";
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+    Mandated = 0x8000, "ACC_MANDATED";
+}
+
+impl ModuleAccessFlags {
+    /// Checks that the module is not both synthetic and mandated.
+    pub fn validate(self) -> Result<(), Vec<AccessFlagsViolation>> {
+        let mut violations = Vec::new();
+        self.conflicts(ModuleFlag::Synthetic, ModuleFlag::Mandated, &mut violations);
+        checked(violations)
+    }
+}
+
+access_flags! {
+    /// A flag of the requires_flags item of a requires entry of the Module
+    /// attribute.
+    flag RequiresFlag;
+    /// The requires_flags item of a requires entry of the Module attribute.
+    set RequiresAccessFlags;
+    Transitive = 0x0020, "ACC_TRANSITIVE";
+    StaticPhase = 0x0040, "ACC_STATIC_PHASE";
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+    Mandated = 0x8000, "ACC_MANDATED";
+}
+
+impl RequiresAccessFlags {
+    /// Checks the flags of the entry that requires the module called
+    /// `module` in a class file of `version`. From version 54 a dependence
+    /// on `java.base` can be neither transitive nor static.
+    pub fn validate(
+        self,
+        module: &str,
+        version: ClassFileVersion,
+    ) -> Result<(), Vec<AccessFlagsViolation>> {
+        use RequiresFlag::*;
+        let mut violations = Vec::new();
+        if module == "java.base" && version.major >= 54 {
+            self.not_allowed(
+                &[Transitive, StaticPhase],
+                "a requires entry for java.base",
+                &mut violations,
+            );
+        }
+        self.conflicts(Synthetic, Mandated, &mut violations);
+        checked(violations)
+    }
+}
+
+access_flags! {
+    /// A flag of the exports_flags or opens_flags item of an exports or
+    /// opens entry of the Module attribute.
+    flag ExportsFlag;
+    /// The exports_flags or opens_flags item of an exports or opens entry of
+    /// the Module attribute.
+    set ExportsAccessFlags;
+    Synthetic = 0x1000, "ACC_SYNTHETIC";
+    Mandated = 0x8000, "ACC_MANDATED";
+}
+
+impl ExportsAccessFlags {
+    /// Checks that the entry is not both synthetic and mandated.
+    pub fn validate(self) -> Result<(), Vec<AccessFlagsViolation>> {
+        let mut violations = Vec::new();
+        self.conflicts(
+            ExportsFlag::Synthetic,
+            ExportsFlag::Mandated,
+            &mut violations,
+        );
+        checked(violations)
+    }
+}
+
+/// Turns the violations a check found into its result.
+fn checked(violations: Vec<AccessFlagsViolation>) -> Result<(), Vec<AccessFlagsViolation>> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+pub fn parse_access_flags(input: u16) -> HashSet<CFAccessFlags> {
+    ClassAccessFlags::from(input).flags().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_every_class_flag() {
+        let flags = parse_access_flags(0x0021);
+        assert!(flags.contains(&CFAccessFlags::Public));
+        assert!(flags.contains(&CFAccessFlags::Super));
+        assert!(!flags.contains(&CFAccessFlags::Final));
+        assert_eq!(
+            parse_access_flags(0x8000),
+            HashSet::from([CFAccessFlags::Module])
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_bits() {
+        let flags = ClassAccessFlags::from(0x0121);
+        assert_eq!(flags.unknown_bits(), 0x0100);
+        assert_eq!(u16::from(flags), 0x0121);
+        assert_eq!(
+            format!("{flags:?}"),
+            "ClassAccessFlags(ACC_PUBLIC | ACC_SUPER | 0x0100)"
+        );
+        assert_eq!(
+            [CFAccessFlags::Public, CFAccessFlags::Final]
+                .into_iter()
+                .collect::<ClassAccessFlags>()
+                .bits(),
+            0x0011
+        );
+    }
+
+    #[test]
+    fn validates_class_flag_combinations() {
+        let java_17 = ClassFileVersion::new(61, 0);
+        assert!(ClassAccessFlags::from(0x0021).validate(java_17).is_ok());
+        assert!(ClassAccessFlags::from(0x0601).validate(java_17).is_ok());
+        assert!(ClassAccessFlags::from(0x2601).validate(java_17).is_ok());
+        assert!(ClassAccessFlags::from(0x8000).validate(java_17).is_ok());
+        assert_eq!(
+            ClassAccessFlags::from(0x0200).validate(java_17),
+            Err(vec![AccessFlagsViolation::Requires {
+                flag: "ACC_INTERFACE",
+                required: "ACC_ABSTRACT",
+            }])
+        );
+        assert_eq!(
+            ClassAccessFlags::from(0x0610).validate(java_17),
+            Err(vec![
+                AccessFlagsViolation::Conflicts {
+                    flag: "ACC_INTERFACE",
+                    other: "ACC_FINAL",
+                },
+                AccessFlagsViolation::Conflicts {
+                    flag: "ACC_FINAL",
+                    other: "ACC_ABSTRACT",
+                },
+            ])
+        );
+        assert_eq!(
+            ClassAccessFlags::from(0x2401).validate(java_17),
+            Err(vec![AccessFlagsViolation::Requires {
+                flag: "ACC_ANNOTATION",
+                required: "ACC_INTERFACE",
+            }])
+        );
+        assert!(ClassAccessFlags::from(0x8001).validate(java_17).is_err());
+        // Flags from later releases are ignored in older class files.
+        let java_8 = ClassFileVersion::new(52, 0);
+        assert!(ClassAccessFlags::from(0x8001).validate(java_8).is_ok());
+        let java_1_4 = ClassFileVersion::new(48, 0);
+        assert!(ClassAccessFlags::from(0x2401).validate(java_1_4).is_ok());
+    }
+
+    #[test]
+    fn validates_member_flag_combinations() {
+        let java_8 = ClassFileVersion::new(52, 0);
+        assert!(
+            FieldAccessFlags::from(0x0019)
+                .validate(true, java_8)
+                .is_ok()
+        );
+        assert_eq!(
+            FieldAccessFlags::from(0x0003).validate(false, java_8),
+            Err(vec![AccessFlagsViolation::Conflicts {
+                flag: "ACC_PUBLIC",
+                other: "ACC_PRIVATE",
+            }])
+        );
+        assert_eq!(
+            FieldAccessFlags::from(0x0049).validate(true, java_8),
+            Err(vec![
+                AccessFlagsViolation::Missing {
+                    required: "ACC_FINAL",
+                    context: "an interface field",
+                },
+                AccessFlagsViolation::NotAllowed {
+                    flag: "ACC_VOLATILE",
+                    context: "an interface field",
+                },
+            ])
+        );
+        assert!(
+            FieldAccessFlags::from(0x4019)
+                .validate(true, ClassFileVersion::new(48, 0))
+                .is_ok()
+        );
+        assert!(
+            FieldAccessFlags::from(0x4019)
+                .validate(true, java_8)
+                .is_err()
+        );
+        let method = MethodAccessFlags::from(0x0401);
+        assert!(method.validate("run", true, java_8).is_ok());
+        assert!(
+            MethodAccessFlags::from(0x0002)
+                .validate("run", true, java_8)
+                .is_ok()
+        );
+        assert!(
+            MethodAccessFlags::from(0x0002)
+                .validate("run", true, ClassFileVersion::new(51, 0))
+                .is_err()
+        );
+        assert!(
+            MethodAccessFlags::from(0x0408)
+                .validate("run", false, java_8)
+                .is_err()
+        );
+        assert!(
+            MethodAccessFlags::from(0x0009)
+                .validate("<init>", false, java_8)
+                .is_err()
+        );
+        assert!(
+            MethodAccessFlags::from(0x0000)
+                .validate("<clinit>", false, java_8)
+                .is_err()
+        );
+        // Only the flags of Java 1.2 to 16 give ACC_STRICT a meaning.
+        assert!(
+            MethodAccessFlags::from(0x0c01)
+                .validate("run", false, ClassFileVersion::new(61, 0))
+                .is_ok()
+        );
+        assert!(
+            MethodAccessFlags::from(0x0c01)
+                .validate("run", false, java_8)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validates_attribute_flag_combinations() {
+        assert!(InnerClassAccessFlags::from(0x0609).validate().is_ok());
+        assert!(InnerClassAccessFlags::from(0x0006).validate().is_err());
+        assert!(ParameterAccessFlags::from(0x9000).validate().is_err());
+        assert!(ModuleAccessFlags::from(0x0020).validate().is_ok());
+        let requires = RequiresAccessFlags::from(0x0020);
+        assert!(
+            requires
+                .validate("java.sql", ClassFileVersion::new(53, 0))
+                .is_ok()
+        );
+        assert!(
+            requires
+                .validate("java.base", ClassFileVersion::new(53, 0))
+                .is_ok()
+        );
+        assert!(
+            requires
+                .validate("java.base", ClassFileVersion::new(54, 0))
+                .is_err()
+        );
+        assert!(ExportsAccessFlags::from(0x8000).validate().is_ok());
+    }
+}
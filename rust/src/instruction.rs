@@ -0,0 +1,1617 @@
+use crate::error::{BytecodeError, ConstantPoolError};
+use crate::{ClassFileVersion, ConstantPool, ConstantPoolItem};
+
+/// A single JVM instruction with its operands as they are encoded.
+///
+/// Branch offsets are relative to the pc of the instruction, and constant
+/// pool operands have been checked to refer to an entry of the right kind.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    Nop,
+    AconstNull,
+    IconstM1,
+    Iconst0,
+    Iconst1,
+    Iconst2,
+    Iconst3,
+    Iconst4,
+    Iconst5,
+    Lconst0,
+    Lconst1,
+    Fconst0,
+    Fconst1,
+    Fconst2,
+    Dconst0,
+    Dconst1,
+    Bipush(i8),
+    Sipush(i16),
+    Ldc(u8),
+    LdcW(u16),
+    Ldc2W(u16),
+    Iload(u8),
+    Lload(u8),
+    Fload(u8),
+    Dload(u8),
+    Aload(u8),
+    Iload0,
+    Iload1,
+    Iload2,
+    Iload3,
+    Lload0,
+    Lload1,
+    Lload2,
+    Lload3,
+    Fload0,
+    Fload1,
+    Fload2,
+    Fload3,
+    Dload0,
+    Dload1,
+    Dload2,
+    Dload3,
+    Aload0,
+    Aload1,
+    Aload2,
+    Aload3,
+    Iaload,
+    Laload,
+    Faload,
+    Daload,
+    Aaload,
+    Baload,
+    Caload,
+    Saload,
+    Istore(u8),
+    Lstore(u8),
+    Fstore(u8),
+    Dstore(u8),
+    Astore(u8),
+    Istore0,
+    Istore1,
+    Istore2,
+    Istore3,
+    Lstore0,
+    Lstore1,
+    Lstore2,
+    Lstore3,
+    Fstore0,
+    Fstore1,
+    Fstore2,
+    Fstore3,
+    Dstore0,
+    Dstore1,
+    Dstore2,
+    Dstore3,
+    Astore0,
+    Astore1,
+    Astore2,
+    Astore3,
+    Iastore,
+    Lastore,
+    Fastore,
+    Dastore,
+    Aastore,
+    Bastore,
+    Castore,
+    Sastore,
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
+    Iadd,
+    Ladd,
+    Fadd,
+    Dadd,
+    Isub,
+    Lsub,
+    Fsub,
+    Dsub,
+    Imul,
+    Lmul,
+    Fmul,
+    Dmul,
+    Idiv,
+    Ldiv,
+    Fdiv,
+    Ddiv,
+    Irem,
+    Lrem,
+    Frem,
+    Drem,
+    Ineg,
+    Lneg,
+    Fneg,
+    Dneg,
+    Ishl,
+    Lshl,
+    Ishr,
+    Lshr,
+    Iushr,
+    Lushr,
+    Iand,
+    Land,
+    Ior,
+    Lor,
+    Ixor,
+    Lxor,
+    Iinc(u8, i8),
+    I2l,
+    I2f,
+    I2d,
+    L2i,
+    L2f,
+    L2d,
+    F2i,
+    F2l,
+    F2d,
+    D2i,
+    D2l,
+    D2f,
+    I2b,
+    I2c,
+    I2s,
+    Lcmp,
+    Fcmpl,
+    Fcmpg,
+    Dcmpl,
+    Dcmpg,
+    Ifeq(i16),
+    Ifne(i16),
+    Iflt(i16),
+    Ifge(i16),
+    Ifgt(i16),
+    Ifle(i16),
+    IfIcmpeq(i16),
+    IfIcmpne(i16),
+    IfIcmplt(i16),
+    IfIcmpge(i16),
+    IfIcmpgt(i16),
+    IfIcmple(i16),
+    IfAcmpeq(i16),
+    IfAcmpne(i16),
+    Goto(i16),
+    Jsr(i16),
+    Ret(u8),
+    Tableswitch {
+        default: i32,
+        low: i32,
+        high: i32,
+        offsets: Vec<i32>,
+    },
+    Lookupswitch {
+        default: i32,
+        pairs: Vec<(i32, i32)>,
+    },
+    Ireturn,
+    Lreturn,
+    Freturn,
+    Dreturn,
+    Areturn,
+    Return,
+    Getstatic(u16),
+    Putstatic(u16),
+    Getfield(u16),
+    Putfield(u16),
+    Invokevirtual(u16),
+    Invokespecial(u16),
+    Invokestatic(u16),
+    Invokeinterface(u16, u8),
+    Invokedynamic(u16),
+    New(u16),
+    Newarray(ArrayType),
+    Anewarray(u16),
+    Arraylength,
+    Athrow,
+    Checkcast(u16),
+    Instanceof(u16),
+    Monitorenter,
+    Monitorexit,
+    Wide(Wide),
+    Multianewarray(u16, u8),
+    Ifnull(i16),
+    Ifnonnull(i16),
+    GotoW(i32),
+    JsrW(i32),
+}
+
+/// An instruction that follows the `wide` prefix, with its widened local
+/// variable index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Wide {
+    Iload(u16),
+    Lload(u16),
+    Fload(u16),
+    Dload(u16),
+    Aload(u16),
+    Istore(u16),
+    Lstore(u16),
+    Fstore(u16),
+    Dstore(u16),
+    Astore(u16),
+    Ret(u16),
+    Iinc(u16, i16),
+}
+
+/// The element type operand of `newarray`.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArrayType {
+    Boolean = 4,
+    Char = 5,
+    Float = 6,
+    Double = 7,
+    Byte = 8,
+    Short = 9,
+    Int = 10,
+    Long = 11,
+}
+
+impl Instruction {
+    /// The opcode of the instruction. For a widened instruction this is the
+    /// opcode of `wide`.
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Instruction::Nop => 0x00,
+            Instruction::AconstNull => 0x01,
+            Instruction::IconstM1 => 0x02,
+            Instruction::Iconst0 => 0x03,
+            Instruction::Iconst1 => 0x04,
+            Instruction::Iconst2 => 0x05,
+            Instruction::Iconst3 => 0x06,
+            Instruction::Iconst4 => 0x07,
+            Instruction::Iconst5 => 0x08,
+            Instruction::Lconst0 => 0x09,
+            Instruction::Lconst1 => 0x0a,
+            Instruction::Fconst0 => 0x0b,
+            Instruction::Fconst1 => 0x0c,
+            Instruction::Fconst2 => 0x0d,
+            Instruction::Dconst0 => 0x0e,
+            Instruction::Dconst1 => 0x0f,
+            Instruction::Bipush(..) => 0x10,
+            Instruction::Sipush(..) => 0x11,
+            Instruction::Ldc(..) => 0x12,
+            Instruction::LdcW(..) => 0x13,
+            Instruction::Ldc2W(..) => 0x14,
+            Instruction::Iload(..) => 0x15,
+            Instruction::Lload(..) => 0x16,
+            Instruction::Fload(..) => 0x17,
+            Instruction::Dload(..) => 0x18,
+            Instruction::Aload(..) => 0x19,
+            Instruction::Iload0 => 0x1a,
+            Instruction::Iload1 => 0x1b,
+            Instruction::Iload2 => 0x1c,
+            Instruction::Iload3 => 0x1d,
+            Instruction::Lload0 => 0x1e,
+            Instruction::Lload1 => 0x1f,
+            Instruction::Lload2 => 0x20,
+            Instruction::Lload3 => 0x21,
+            Instruction::Fload0 => 0x22,
+            Instruction::Fload1 => 0x23,
+            Instruction::Fload2 => 0x24,
+            Instruction::Fload3 => 0x25,
+            Instruction::Dload0 => 0x26,
+            Instruction::Dload1 => 0x27,
+            Instruction::Dload2 => 0x28,
+            Instruction::Dload3 => 0x29,
+            Instruction::Aload0 => 0x2a,
+            Instruction::Aload1 => 0x2b,
+            Instruction::Aload2 => 0x2c,
+            Instruction::Aload3 => 0x2d,
+            Instruction::Iaload => 0x2e,
+            Instruction::Laload => 0x2f,
+            Instruction::Faload => 0x30,
+            Instruction::Daload => 0x31,
+            Instruction::Aaload => 0x32,
+            Instruction::Baload => 0x33,
+            Instruction::Caload => 0x34,
+            Instruction::Saload => 0x35,
+            Instruction::Istore(..) => 0x36,
+            Instruction::Lstore(..) => 0x37,
+            Instruction::Fstore(..) => 0x38,
+            Instruction::Dstore(..) => 0x39,
+            Instruction::Astore(..) => 0x3a,
+            Instruction::Istore0 => 0x3b,
+            Instruction::Istore1 => 0x3c,
+            Instruction::Istore2 => 0x3d,
+            Instruction::Istore3 => 0x3e,
+            Instruction::Lstore0 => 0x3f,
+            Instruction::Lstore1 => 0x40,
+            Instruction::Lstore2 => 0x41,
+            Instruction::Lstore3 => 0x42,
+            Instruction::Fstore0 => 0x43,
+            Instruction::Fstore1 => 0x44,
+            Instruction::Fstore2 => 0x45,
+            Instruction::Fstore3 => 0x46,
+            Instruction::Dstore0 => 0x47,
+            Instruction::Dstore1 => 0x48,
+            Instruction::Dstore2 => 0x49,
+            Instruction::Dstore3 => 0x4a,
+            Instruction::Astore0 => 0x4b,
+            Instruction::Astore1 => 0x4c,
+            Instruction::Astore2 => 0x4d,
+            Instruction::Astore3 => 0x4e,
+            Instruction::Iastore => 0x4f,
+            Instruction::Lastore => 0x50,
+            Instruction::Fastore => 0x51,
+            Instruction::Dastore => 0x52,
+            Instruction::Aastore => 0x53,
+            Instruction::Bastore => 0x54,
+            Instruction::Castore => 0x55,
+            Instruction::Sastore => 0x56,
+            Instruction::Pop => 0x57,
+            Instruction::Pop2 => 0x58,
+            Instruction::Dup => 0x59,
+            Instruction::DupX1 => 0x5a,
+            Instruction::DupX2 => 0x5b,
+            Instruction::Dup2 => 0x5c,
+            Instruction::Dup2X1 => 0x5d,
+            Instruction::Dup2X2 => 0x5e,
+            Instruction::Swap => 0x5f,
+            Instruction::Iadd => 0x60,
+            Instruction::Ladd => 0x61,
+            Instruction::Fadd => 0x62,
+            Instruction::Dadd => 0x63,
+            Instruction::Isub => 0x64,
+            Instruction::Lsub => 0x65,
+            Instruction::Fsub => 0x66,
+            Instruction::Dsub => 0x67,
+            Instruction::Imul => 0x68,
+            Instruction::Lmul => 0x69,
+            Instruction::Fmul => 0x6a,
+            Instruction::Dmul => 0x6b,
+            Instruction::Idiv => 0x6c,
+            Instruction::Ldiv => 0x6d,
+            Instruction::Fdiv => 0x6e,
+            Instruction::Ddiv => 0x6f,
+            Instruction::Irem => 0x70,
+            Instruction::Lrem => 0x71,
+            Instruction::Frem => 0x72,
+            Instruction::Drem => 0x73,
+            Instruction::Ineg => 0x74,
+            Instruction::Lneg => 0x75,
+            Instruction::Fneg => 0x76,
+            Instruction::Dneg => 0x77,
+            Instruction::Ishl => 0x78,
+            Instruction::Lshl => 0x79,
+            Instruction::Ishr => 0x7a,
+            Instruction::Lshr => 0x7b,
+            Instruction::Iushr => 0x7c,
+            Instruction::Lushr => 0x7d,
+            Instruction::Iand => 0x7e,
+            Instruction::Land => 0x7f,
+            Instruction::Ior => 0x80,
+            Instruction::Lor => 0x81,
+            Instruction::Ixor => 0x82,
+            Instruction::Lxor => 0x83,
+            Instruction::Iinc(..) => 0x84,
+            Instruction::I2l => 0x85,
+            Instruction::I2f => 0x86,
+            Instruction::I2d => 0x87,
+            Instruction::L2i => 0x88,
+            Instruction::L2f => 0x89,
+            Instruction::L2d => 0x8a,
+            Instruction::F2i => 0x8b,
+            Instruction::F2l => 0x8c,
+            Instruction::F2d => 0x8d,
+            Instruction::D2i => 0x8e,
+            Instruction::D2l => 0x8f,
+            Instruction::D2f => 0x90,
+            Instruction::I2b => 0x91,
+            Instruction::I2c => 0x92,
+            Instruction::I2s => 0x93,
+            Instruction::Lcmp => 0x94,
+            Instruction::Fcmpl => 0x95,
+            Instruction::Fcmpg => 0x96,
+            Instruction::Dcmpl => 0x97,
+            Instruction::Dcmpg => 0x98,
+            Instruction::Ifeq(..) => 0x99,
+            Instruction::Ifne(..) => 0x9a,
+            Instruction::Iflt(..) => 0x9b,
+            Instruction::Ifge(..) => 0x9c,
+            Instruction::Ifgt(..) => 0x9d,
+            Instruction::Ifle(..) => 0x9e,
+            Instruction::IfIcmpeq(..) => 0x9f,
+            Instruction::IfIcmpne(..) => 0xa0,
+            Instruction::IfIcmplt(..) => 0xa1,
+            Instruction::IfIcmpge(..) => 0xa2,
+            Instruction::IfIcmpgt(..) => 0xa3,
+            Instruction::IfIcmple(..) => 0xa4,
+            Instruction::IfAcmpeq(..) => 0xa5,
+            Instruction::IfAcmpne(..) => 0xa6,
+            Instruction::Goto(..) => 0xa7,
+            Instruction::Jsr(..) => 0xa8,
+            Instruction::Ret(..) => 0xa9,
+            Instruction::Tableswitch { .. } => 0xaa,
+            Instruction::Lookupswitch { .. } => 0xab,
+            Instruction::Ireturn => 0xac,
+            Instruction::Lreturn => 0xad,
+            Instruction::Freturn => 0xae,
+            Instruction::Dreturn => 0xaf,
+            Instruction::Areturn => 0xb0,
+            Instruction::Return => 0xb1,
+            Instruction::Getstatic(..) => 0xb2,
+            Instruction::Putstatic(..) => 0xb3,
+            Instruction::Getfield(..) => 0xb4,
+            Instruction::Putfield(..) => 0xb5,
+            Instruction::Invokevirtual(..) => 0xb6,
+            Instruction::Invokespecial(..) => 0xb7,
+            Instruction::Invokestatic(..) => 0xb8,
+            Instruction::Invokeinterface(..) => 0xb9,
+            Instruction::Invokedynamic(..) => 0xba,
+            Instruction::New(..) => 0xbb,
+            Instruction::Newarray(..) => 0xbc,
+            Instruction::Anewarray(..) => 0xbd,
+            Instruction::Arraylength => 0xbe,
+            Instruction::Athrow => 0xbf,
+            Instruction::Checkcast(..) => 0xc0,
+            Instruction::Instanceof(..) => 0xc1,
+            Instruction::Monitorenter => 0xc2,
+            Instruction::Monitorexit => 0xc3,
+            Instruction::Wide(..) => 0xc4,
+            Instruction::Multianewarray(..) => 0xc5,
+            Instruction::Ifnull(..) => 0xc6,
+            Instruction::Ifnonnull(..) => 0xc7,
+            Instruction::GotoW(..) => 0xc8,
+            Instruction::JsrW(..) => 0xc9,
+        }
+    }
+
+    /// The mnemonic of the instruction as used by the JVMS.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Nop => "nop",
+            Instruction::AconstNull => "aconst_null",
+            Instruction::IconstM1 => "iconst_m1",
+            Instruction::Iconst0 => "iconst_0",
+            Instruction::Iconst1 => "iconst_1",
+            Instruction::Iconst2 => "iconst_2",
+            Instruction::Iconst3 => "iconst_3",
+            Instruction::Iconst4 => "iconst_4",
+            Instruction::Iconst5 => "iconst_5",
+            Instruction::Lconst0 => "lconst_0",
+            Instruction::Lconst1 => "lconst_1",
+            Instruction::Fconst0 => "fconst_0",
+            Instruction::Fconst1 => "fconst_1",
+            Instruction::Fconst2 => "fconst_2",
+            Instruction::Dconst0 => "dconst_0",
+            Instruction::Dconst1 => "dconst_1",
+            Instruction::Bipush(..) => "bipush",
+            Instruction::Sipush(..) => "sipush",
+            Instruction::Ldc(..) => "ldc",
+            Instruction::LdcW(..) => "ldc_w",
+            Instruction::Ldc2W(..) => "ldc2_w",
+            Instruction::Iload(..) => "iload",
+            Instruction::Lload(..) => "lload",
+            Instruction::Fload(..) => "fload",
+            Instruction::Dload(..) => "dload",
+            Instruction::Aload(..) => "aload",
+            Instruction::Iload0 => "iload_0",
+            Instruction::Iload1 => "iload_1",
+            Instruction::Iload2 => "iload_2",
+            Instruction::Iload3 => "iload_3",
+            Instruction::Lload0 => "lload_0",
+            Instruction::Lload1 => "lload_1",
+            Instruction::Lload2 => "lload_2",
+            Instruction::Lload3 => "lload_3",
+            Instruction::Fload0 => "fload_0",
+            Instruction::Fload1 => "fload_1",
+            Instruction::Fload2 => "fload_2",
+            Instruction::Fload3 => "fload_3",
+            Instruction::Dload0 => "dload_0",
+            Instruction::Dload1 => "dload_1",
+            Instruction::Dload2 => "dload_2",
+            Instruction::Dload3 => "dload_3",
+            Instruction::Aload0 => "aload_0",
+            Instruction::Aload1 => "aload_1",
+            Instruction::Aload2 => "aload_2",
+            Instruction::Aload3 => "aload_3",
+            Instruction::Iaload => "iaload",
+            Instruction::Laload => "laload",
+            Instruction::Faload => "faload",
+            Instruction::Daload => "daload",
+            Instruction::Aaload => "aaload",
+            Instruction::Baload => "baload",
+            Instruction::Caload => "caload",
+            Instruction::Saload => "saload",
+            Instruction::Istore(..) => "istore",
+            Instruction::Lstore(..) => "lstore",
+            Instruction::Fstore(..) => "fstore",
+            Instruction::Dstore(..) => "dstore",
+            Instruction::Astore(..) => "astore",
+            Instruction::Istore0 => "istore_0",
+            Instruction::Istore1 => "istore_1",
+            Instruction::Istore2 => "istore_2",
+            Instruction::Istore3 => "istore_3",
+            Instruction::Lstore0 => "lstore_0",
+            Instruction::Lstore1 => "lstore_1",
+            Instruction::Lstore2 => "lstore_2",
+            Instruction::Lstore3 => "lstore_3",
+            Instruction::Fstore0 => "fstore_0",
+            Instruction::Fstore1 => "fstore_1",
+            Instruction::Fstore2 => "fstore_2",
+            Instruction::Fstore3 => "fstore_3",
+            Instruction::Dstore0 => "dstore_0",
+            Instruction::Dstore1 => "dstore_1",
+            Instruction::Dstore2 => "dstore_2",
+            Instruction::Dstore3 => "dstore_3",
+            Instruction::Astore0 => "astore_0",
+            Instruction::Astore1 => "astore_1",
+            Instruction::Astore2 => "astore_2",
+            Instruction::Astore3 => "astore_3",
+            Instruction::Iastore => "iastore",
+            Instruction::Lastore => "lastore",
+            Instruction::Fastore => "fastore",
+            Instruction::Dastore => "dastore",
+            Instruction::Aastore => "aastore",
+            Instruction::Bastore => "bastore",
+            Instruction::Castore => "castore",
+            Instruction::Sastore => "sastore",
+            Instruction::Pop => "pop",
+            Instruction::Pop2 => "pop2",
+            Instruction::Dup => "dup",
+            Instruction::DupX1 => "dup_x1",
+            Instruction::DupX2 => "dup_x2",
+            Instruction::Dup2 => "dup2",
+            Instruction::Dup2X1 => "dup2_x1",
+            Instruction::Dup2X2 => "dup2_x2",
+            Instruction::Swap => "swap",
+            Instruction::Iadd => "iadd",
+            Instruction::Ladd => "ladd",
+            Instruction::Fadd => "fadd",
+            Instruction::Dadd => "dadd",
+            Instruction::Isub => "isub",
+            Instruction::Lsub => "lsub",
+            Instruction::Fsub => "fsub",
+            Instruction::Dsub => "dsub",
+            Instruction::Imul => "imul",
+            Instruction::Lmul => "lmul",
+            Instruction::Fmul => "fmul",
+            Instruction::Dmul => "dmul",
+            Instruction::Idiv => "idiv",
+            Instruction::Ldiv => "ldiv",
+            Instruction::Fdiv => "fdiv",
+            Instruction::Ddiv => "ddiv",
+            Instruction::Irem => "irem",
+            Instruction::Lrem => "lrem",
+            Instruction::Frem => "frem",
+            Instruction::Drem => "drem",
+            Instruction::Ineg => "ineg",
+            Instruction::Lneg => "lneg",
+            Instruction::Fneg => "fneg",
+            Instruction::Dneg => "dneg",
+            Instruction::Ishl => "ishl",
+            Instruction::Lshl => "lshl",
+            Instruction::Ishr => "ishr",
+            Instruction::Lshr => "lshr",
+            Instruction::Iushr => "iushr",
+            Instruction::Lushr => "lushr",
+            Instruction::Iand => "iand",
+            Instruction::Land => "land",
+            Instruction::Ior => "ior",
+            Instruction::Lor => "lor",
+            Instruction::Ixor => "ixor",
+            Instruction::Lxor => "lxor",
+            Instruction::Iinc(..) => "iinc",
+            Instruction::I2l => "i2l",
+            Instruction::I2f => "i2f",
+            Instruction::I2d => "i2d",
+            Instruction::L2i => "l2i",
+            Instruction::L2f => "l2f",
+            Instruction::L2d => "l2d",
+            Instruction::F2i => "f2i",
+            Instruction::F2l => "f2l",
+            Instruction::F2d => "f2d",
+            Instruction::D2i => "d2i",
+            Instruction::D2l => "d2l",
+            Instruction::D2f => "d2f",
+            Instruction::I2b => "i2b",
+            Instruction::I2c => "i2c",
+            Instruction::I2s => "i2s",
+            Instruction::Lcmp => "lcmp",
+            Instruction::Fcmpl => "fcmpl",
+            Instruction::Fcmpg => "fcmpg",
+            Instruction::Dcmpl => "dcmpl",
+            Instruction::Dcmpg => "dcmpg",
+            Instruction::Ifeq(..) => "ifeq",
+            Instruction::Ifne(..) => "ifne",
+            Instruction::Iflt(..) => "iflt",
+            Instruction::Ifge(..) => "ifge",
+            Instruction::Ifgt(..) => "ifgt",
+            Instruction::Ifle(..) => "ifle",
+            Instruction::IfIcmpeq(..) => "if_icmpeq",
+            Instruction::IfIcmpne(..) => "if_icmpne",
+            Instruction::IfIcmplt(..) => "if_icmplt",
+            Instruction::IfIcmpge(..) => "if_icmpge",
+            Instruction::IfIcmpgt(..) => "if_icmpgt",
+            Instruction::IfIcmple(..) => "if_icmple",
+            Instruction::IfAcmpeq(..) => "if_acmpeq",
+            Instruction::IfAcmpne(..) => "if_acmpne",
+            Instruction::Goto(..) => "goto",
+            Instruction::Jsr(..) => "jsr",
+            Instruction::Ret(..) => "ret",
+            Instruction::Tableswitch { .. } => "tableswitch",
+            Instruction::Lookupswitch { .. } => "lookupswitch",
+            Instruction::Ireturn => "ireturn",
+            Instruction::Lreturn => "lreturn",
+            Instruction::Freturn => "freturn",
+            Instruction::Dreturn => "dreturn",
+            Instruction::Areturn => "areturn",
+            Instruction::Return => "return",
+            Instruction::Getstatic(..) => "getstatic",
+            Instruction::Putstatic(..) => "putstatic",
+            Instruction::Getfield(..) => "getfield",
+            Instruction::Putfield(..) => "putfield",
+            Instruction::Invokevirtual(..) => "invokevirtual",
+            Instruction::Invokespecial(..) => "invokespecial",
+            Instruction::Invokestatic(..) => "invokestatic",
+            Instruction::Invokeinterface(..) => "invokeinterface",
+            Instruction::Invokedynamic(..) => "invokedynamic",
+            Instruction::New(..) => "new",
+            Instruction::Newarray(..) => "newarray",
+            Instruction::Anewarray(..) => "anewarray",
+            Instruction::Arraylength => "arraylength",
+            Instruction::Athrow => "athrow",
+            Instruction::Checkcast(..) => "checkcast",
+            Instruction::Instanceof(..) => "instanceof",
+            Instruction::Monitorenter => "monitorenter",
+            Instruction::Monitorexit => "monitorexit",
+            Instruction::Wide(..) => "wide",
+            Instruction::Multianewarray(..) => "multianewarray",
+            Instruction::Ifnull(..) => "ifnull",
+            Instruction::Ifnonnull(..) => "ifnonnull",
+            Instruction::GotoW(..) => "goto_w",
+            Instruction::JsrW(..) => "jsr_w",
+        }
+    }
+}
+
+impl Wide {
+    /// The opcode of the instruction that is widened.
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Wide::Iload(_) => 0x15,
+            Wide::Lload(_) => 0x16,
+            Wide::Fload(_) => 0x17,
+            Wide::Dload(_) => 0x18,
+            Wide::Aload(_) => 0x19,
+            Wide::Istore(_) => 0x36,
+            Wide::Lstore(_) => 0x37,
+            Wide::Fstore(_) => 0x38,
+            Wide::Dstore(_) => 0x39,
+            Wide::Astore(_) => 0x3a,
+            Wide::Ret(_) => 0xa9,
+            Wide::Iinc(..) => 0x84,
+        }
+    }
+
+    /// The mnemonic of the instruction that is widened.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Wide::Iload(_) => "iload",
+            Wide::Lload(_) => "lload",
+            Wide::Fload(_) => "fload",
+            Wide::Dload(_) => "dload",
+            Wide::Aload(_) => "aload",
+            Wide::Istore(_) => "istore",
+            Wide::Lstore(_) => "lstore",
+            Wide::Fstore(_) => "fstore",
+            Wide::Dstore(_) => "dstore",
+            Wide::Astore(_) => "astore",
+            Wide::Ret(_) => "ret",
+            Wide::Iinc(..) => "iinc",
+        }
+    }
+}
+
+impl ArrayType {
+    fn from_u8(atype: u8) -> Option<ArrayType> {
+        match atype {
+            4 => Some(ArrayType::Boolean),
+            5 => Some(ArrayType::Char),
+            6 => Some(ArrayType::Float),
+            7 => Some(ArrayType::Double),
+            8 => Some(ArrayType::Byte),
+            9 => Some(ArrayType::Short),
+            10 => Some(ArrayType::Int),
+            11 => Some(ArrayType::Long),
+            _ => None,
+        }
+    }
+
+    /// The name of the element type as written in Java source.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArrayType::Boolean => "boolean",
+            ArrayType::Char => "char",
+            ArrayType::Float => "float",
+            ArrayType::Double => "double",
+            ArrayType::Byte => "byte",
+            ArrayType::Short => "short",
+            ArrayType::Int => "int",
+            ArrayType::Long => "long",
+        }
+    }
+}
+
+/// The kinds of constant pool entry an instruction operand can refer to.
+#[derive(Clone, Copy)]
+enum Operand {
+    Field,
+    Method,
+    /// A method of a class or, from version 52 on, of an interface.
+    AnyMethod,
+    InterfaceMethod,
+    InvokeDynamic,
+    Class,
+}
+
+/// Reads the operands of the instruction at `pc`.
+struct Cursor<'a> {
+    code: &'a [u8],
+    pc: usize,
+    position: usize,
+}
+
+impl Cursor<'_> {
+    fn truncated(&self) -> BytecodeError {
+        BytecodeError::Truncated { pc: self.pc as u16 }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], BytecodeError> {
+        let bytes = self
+            .code
+            .get(self.position..self.position + N)
+            .ok_or(self.truncated())?;
+        self.position += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, BytecodeError> {
+        Ok(u16::from_be_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, BytecodeError> {
+        Ok(u32::from_be_bytes(self.bytes()?))
+    }
+
+    fn remaining(&self) -> usize {
+        self.code.len().saturating_sub(self.position)
+    }
+}
+
+/// An iterator over the instructions of a method body and the pc each one
+/// starts at.
+///
+/// Decoding stops after the first error. Only the first 65536 bytes of code
+/// can be addressed by a pc, which is all a Code attribute may hold.
+pub struct Instructions<'a> {
+    code: &'a [u8],
+    constant_pool: &'a ConstantPool,
+    version: ClassFileVersion,
+    pc: usize,
+    failed: bool,
+}
+
+impl<'a> Instructions<'a> {
+    /// Decodes `code` from a class file with the given version, checking
+    /// constant pool operands against `constant_pool`.
+    pub fn new(
+        code: &'a [u8],
+        constant_pool: &'a ConstantPool,
+        version: ClassFileVersion,
+    ) -> Instructions<'a> {
+        Instructions {
+            code,
+            constant_pool,
+            version,
+            pc: 0,
+            failed: false,
+        }
+    }
+
+    fn decode(&self, pc: usize) -> Result<(Instruction, usize), BytecodeError> {
+        let mut cursor = Cursor {
+            code: self.code,
+            pc,
+            position: pc + 1,
+        };
+        let instruction = match self.code[pc] {
+            0x00 => Instruction::Nop,
+            0x01 => Instruction::AconstNull,
+            0x02 => Instruction::IconstM1,
+            0x03 => Instruction::Iconst0,
+            0x04 => Instruction::Iconst1,
+            0x05 => Instruction::Iconst2,
+            0x06 => Instruction::Iconst3,
+            0x07 => Instruction::Iconst4,
+            0x08 => Instruction::Iconst5,
+            0x09 => Instruction::Lconst0,
+            0x0a => Instruction::Lconst1,
+            0x0b => Instruction::Fconst0,
+            0x0c => Instruction::Fconst1,
+            0x0d => Instruction::Fconst2,
+            0x0e => Instruction::Dconst0,
+            0x0f => Instruction::Dconst1,
+            0x10 => Instruction::Bipush(cursor.u8()? as i8),
+            0x11 => Instruction::Sipush(cursor.u16()? as i16),
+            0x12 => {
+                let index = cursor.u8()?;
+                self.loadable(pc, index as u16, false)?;
+                Instruction::Ldc(index)
+            }
+            0x13 => Instruction::LdcW(self.loadable(pc, cursor.u16()?, false)?),
+            0x14 => Instruction::Ldc2W(self.loadable(pc, cursor.u16()?, true)?),
+            0x15 => Instruction::Iload(cursor.u8()?),
+            0x16 => Instruction::Lload(cursor.u8()?),
+            0x17 => Instruction::Fload(cursor.u8()?),
+            0x18 => Instruction::Dload(cursor.u8()?),
+            0x19 => Instruction::Aload(cursor.u8()?),
+            0x1a => Instruction::Iload0,
+            0x1b => Instruction::Iload1,
+            0x1c => Instruction::Iload2,
+            0x1d => Instruction::Iload3,
+            0x1e => Instruction::Lload0,
+            0x1f => Instruction::Lload1,
+            0x20 => Instruction::Lload2,
+            0x21 => Instruction::Lload3,
+            0x22 => Instruction::Fload0,
+            0x23 => Instruction::Fload1,
+            0x24 => Instruction::Fload2,
+            0x25 => Instruction::Fload3,
+            0x26 => Instruction::Dload0,
+            0x27 => Instruction::Dload1,
+            0x28 => Instruction::Dload2,
+            0x29 => Instruction::Dload3,
+            0x2a => Instruction::Aload0,
+            0x2b => Instruction::Aload1,
+            0x2c => Instruction::Aload2,
+            0x2d => Instruction::Aload3,
+            0x2e => Instruction::Iaload,
+            0x2f => Instruction::Laload,
+            0x30 => Instruction::Faload,
+            0x31 => Instruction::Daload,
+            0x32 => Instruction::Aaload,
+            0x33 => Instruction::Baload,
+            0x34 => Instruction::Caload,
+            0x35 => Instruction::Saload,
+            0x36 => Instruction::Istore(cursor.u8()?),
+            0x37 => Instruction::Lstore(cursor.u8()?),
+            0x38 => Instruction::Fstore(cursor.u8()?),
+            0x39 => Instruction::Dstore(cursor.u8()?),
+            0x3a => Instruction::Astore(cursor.u8()?),
+            0x3b => Instruction::Istore0,
+            0x3c => Instruction::Istore1,
+            0x3d => Instruction::Istore2,
+            0x3e => Instruction::Istore3,
+            0x3f => Instruction::Lstore0,
+            0x40 => Instruction::Lstore1,
+            0x41 => Instruction::Lstore2,
+            0x42 => Instruction::Lstore3,
+            0x43 => Instruction::Fstore0,
+            0x44 => Instruction::Fstore1,
+            0x45 => Instruction::Fstore2,
+            0x46 => Instruction::Fstore3,
+            0x47 => Instruction::Dstore0,
+            0x48 => Instruction::Dstore1,
+            0x49 => Instruction::Dstore2,
+            0x4a => Instruction::Dstore3,
+            0x4b => Instruction::Astore0,
+            0x4c => Instruction::Astore1,
+            0x4d => Instruction::Astore2,
+            0x4e => Instruction::Astore3,
+            0x4f => Instruction::Iastore,
+            0x50 => Instruction::Lastore,
+            0x51 => Instruction::Fastore,
+            0x52 => Instruction::Dastore,
+            0x53 => Instruction::Aastore,
+            0x54 => Instruction::Bastore,
+            0x55 => Instruction::Castore,
+            0x56 => Instruction::Sastore,
+            0x57 => Instruction::Pop,
+            0x58 => Instruction::Pop2,
+            0x59 => Instruction::Dup,
+            0x5a => Instruction::DupX1,
+            0x5b => Instruction::DupX2,
+            0x5c => Instruction::Dup2,
+            0x5d => Instruction::Dup2X1,
+            0x5e => Instruction::Dup2X2,
+            0x5f => Instruction::Swap,
+            0x60 => Instruction::Iadd,
+            0x61 => Instruction::Ladd,
+            0x62 => Instruction::Fadd,
+            0x63 => Instruction::Dadd,
+            0x64 => Instruction::Isub,
+            0x65 => Instruction::Lsub,
+            0x66 => Instruction::Fsub,
+            0x67 => Instruction::Dsub,
+            0x68 => Instruction::Imul,
+            0x69 => Instruction::Lmul,
+            0x6a => Instruction::Fmul,
+            0x6b => Instruction::Dmul,
+            0x6c => Instruction::Idiv,
+            0x6d => Instruction::Ldiv,
+            0x6e => Instruction::Fdiv,
+            0x6f => Instruction::Ddiv,
+            0x70 => Instruction::Irem,
+            0x71 => Instruction::Lrem,
+            0x72 => Instruction::Frem,
+            0x73 => Instruction::Drem,
+            0x74 => Instruction::Ineg,
+            0x75 => Instruction::Lneg,
+            0x76 => Instruction::Fneg,
+            0x77 => Instruction::Dneg,
+            0x78 => Instruction::Ishl,
+            0x79 => Instruction::Lshl,
+            0x7a => Instruction::Ishr,
+            0x7b => Instruction::Lshr,
+            0x7c => Instruction::Iushr,
+            0x7d => Instruction::Lushr,
+            0x7e => Instruction::Iand,
+            0x7f => Instruction::Land,
+            0x80 => Instruction::Ior,
+            0x81 => Instruction::Lor,
+            0x82 => Instruction::Ixor,
+            0x83 => Instruction::Lxor,
+            0x84 => Instruction::Iinc(cursor.u8()?, cursor.u8()? as i8),
+            0x85 => Instruction::I2l,
+            0x86 => Instruction::I2f,
+            0x87 => Instruction::I2d,
+            0x88 => Instruction::L2i,
+            0x89 => Instruction::L2f,
+            0x8a => Instruction::L2d,
+            0x8b => Instruction::F2i,
+            0x8c => Instruction::F2l,
+            0x8d => Instruction::F2d,
+            0x8e => Instruction::D2i,
+            0x8f => Instruction::D2l,
+            0x90 => Instruction::D2f,
+            0x91 => Instruction::I2b,
+            0x92 => Instruction::I2c,
+            0x93 => Instruction::I2s,
+            0x94 => Instruction::Lcmp,
+            0x95 => Instruction::Fcmpl,
+            0x96 => Instruction::Fcmpg,
+            0x97 => Instruction::Dcmpl,
+            0x98 => Instruction::Dcmpg,
+            0x99 => Instruction::Ifeq(cursor.u16()? as i16),
+            0x9a => Instruction::Ifne(cursor.u16()? as i16),
+            0x9b => Instruction::Iflt(cursor.u16()? as i16),
+            0x9c => Instruction::Ifge(cursor.u16()? as i16),
+            0x9d => Instruction::Ifgt(cursor.u16()? as i16),
+            0x9e => Instruction::Ifle(cursor.u16()? as i16),
+            0x9f => Instruction::IfIcmpeq(cursor.u16()? as i16),
+            0xa0 => Instruction::IfIcmpne(cursor.u16()? as i16),
+            0xa1 => Instruction::IfIcmplt(cursor.u16()? as i16),
+            0xa2 => Instruction::IfIcmpge(cursor.u16()? as i16),
+            0xa3 => Instruction::IfIcmpgt(cursor.u16()? as i16),
+            0xa4 => Instruction::IfIcmple(cursor.u16()? as i16),
+            0xa5 => Instruction::IfAcmpeq(cursor.u16()? as i16),
+            0xa6 => Instruction::IfAcmpne(cursor.u16()? as i16),
+            0xa7 => Instruction::Goto(cursor.u16()? as i16),
+            0xa8 => Instruction::Jsr(cursor.u16()? as i16),
+            0xa9 => Instruction::Ret(cursor.u8()?),
+            0xaa => self.tableswitch(pc, &mut cursor)?,
+            0xab => self.lookupswitch(pc, &mut cursor)?,
+            0xac => Instruction::Ireturn,
+            0xad => Instruction::Lreturn,
+            0xae => Instruction::Freturn,
+            0xaf => Instruction::Dreturn,
+            0xb0 => Instruction::Areturn,
+            0xb1 => Instruction::Return,
+            0xb2 => Instruction::Getstatic(self.constant(pc, cursor.u16()?, Operand::Field)?),
+            0xb3 => Instruction::Putstatic(self.constant(pc, cursor.u16()?, Operand::Field)?),
+            0xb4 => Instruction::Getfield(self.constant(pc, cursor.u16()?, Operand::Field)?),
+            0xb5 => Instruction::Putfield(self.constant(pc, cursor.u16()?, Operand::Field)?),
+            0xb6 => {
+                Instruction::Invokevirtual(self.constant(pc, cursor.u16()?, Operand::Method)?)
+            }
+            0xb7 => Instruction::Invokespecial(self.constant(
+                pc,
+                cursor.u16()?,
+                self.special_or_static_operand(),
+            )?),
+            0xb8 => Instruction::Invokestatic(self.constant(
+                pc,
+                cursor.u16()?,
+                self.special_or_static_operand(),
+            )?),
+            0xb9 => self.invokeinterface(pc, &mut cursor)?,
+            0xba => self.invokedynamic(pc, &mut cursor)?,
+            0xbb => Instruction::New(self.constant(pc, cursor.u16()?, Operand::Class)?),
+            0xbc => self.newarray(pc, &mut cursor)?,
+            0xbd => Instruction::Anewarray(self.constant(pc, cursor.u16()?, Operand::Class)?),
+            0xbe => Instruction::Arraylength,
+            0xbf => Instruction::Athrow,
+            0xc0 => Instruction::Checkcast(self.constant(pc, cursor.u16()?, Operand::Class)?),
+            0xc1 => Instruction::Instanceof(self.constant(pc, cursor.u16()?, Operand::Class)?),
+            0xc2 => Instruction::Monitorenter,
+            0xc3 => Instruction::Monitorexit,
+            0xc4 => self.wide(pc, &mut cursor)?,
+            0xc5 => self.multianewarray(pc, &mut cursor)?,
+            0xc6 => Instruction::Ifnull(cursor.u16()? as i16),
+            0xc7 => Instruction::Ifnonnull(cursor.u16()? as i16),
+            0xc8 => Instruction::GotoW(cursor.u32()? as i32),
+            0xc9 => Instruction::JsrW(cursor.u32()? as i32),
+            opcode => {
+                return Err(BytecodeError::UnknownOpcode {
+                    pc: pc as u16,
+                    opcode,
+                });
+            }
+        };
+        Ok((instruction, cursor.position))
+    }
+
+    /// The kind of method `invokespecial` and `invokestatic` may call, which
+    /// only includes interface methods from version 52 on (JVMS §4.9.1).
+    fn special_or_static_operand(&self) -> Operand {
+        if self.version.major >= 52 {
+            Operand::AnyMethod
+        } else {
+            Operand::Method
+        }
+    }
+
+    /// Checks that `index` refers to an entry of the kind `operand` needs.
+    fn constant(&self, pc: usize, index: u16, operand: Operand) -> Result<u16, BytecodeError> {
+        let item = self.get(pc, index)?;
+        let (matches, expected) = match operand {
+            Operand::Field => (matches!(item, ConstantPoolItem::Fieldref(..)), "Fieldref"),
+            Operand::Method => (matches!(item, ConstantPoolItem::Methodref(..)), "Methodref"),
+            Operand::AnyMethod => (
+                matches!(
+                    item,
+                    ConstantPoolItem::Methodref(..) | ConstantPoolItem::InterfaceMethodref(..)
+                ),
+                "Methodref or InterfaceMethodref",
+            ),
+            Operand::InterfaceMethod => (
+                matches!(item, ConstantPoolItem::InterfaceMethodref(..)),
+                "InterfaceMethodref",
+            ),
+            Operand::InvokeDynamic => (
+                matches!(item, ConstantPoolItem::InvokeDynamic(..)),
+                "InvokeDynamic",
+            ),
+            Operand::Class => (matches!(item, ConstantPoolItem::Class(_)), "Class"),
+        };
+        if !matches {
+            return Err(BytecodeError::Constant {
+                pc: pc as u16,
+                error: ConstantPoolError::UnexpectedType { index, expected },
+            });
+        }
+        Ok(index)
+    }
+
+    /// Checks that `index` refers to a constant `ldc` or `ldc_w` can load,
+    /// or one that `ldc2_w` can load when `wide` is set.
+    fn loadable(&self, pc: usize, index: u16, wide: bool) -> Result<u16, BytecodeError> {
+        let expected = if wide {
+            "Long, Double or Dynamic of type long or double"
+        } else {
+            "loadable constant of a category 1 type"
+        };
+        let category2 = match self.get(pc, index)? {
+            ConstantPoolItem::Integer(_)
+            | ConstantPoolItem::Float(_)
+            | ConstantPoolItem::String(_)
+            | ConstantPoolItem::Class(_)
+            | ConstantPoolItem::MethodHandle(..)
+            | ConstantPoolItem::MethodType(_) => false,
+            ConstantPoolItem::Long(_) | ConstantPoolItem::Double(_) => true,
+            ConstantPoolItem::Dynamic(_, name_and_type) => {
+                let (_, descriptor) =
+                    self.constant_pool
+                        .name_and_type(*name_and_type)
+                        .map_err(|error| BytecodeError::Constant {
+                            pc: pc as u16,
+                            error,
+                        })?;
+                descriptor == "J" || descriptor == "D"
+            }
+            _ => !wide,
+        };
+        if category2 != wide {
+            return Err(BytecodeError::Constant {
+                pc: pc as u16,
+                error: ConstantPoolError::UnexpectedType { index, expected },
+            });
+        }
+        Ok(index)
+    }
+
+    fn get(&self, pc: usize, index: u16) -> Result<&'a ConstantPoolItem, BytecodeError> {
+        self.constant_pool
+            .get(index)
+            .map_err(|error| BytecodeError::Constant {
+                pc: pc as u16,
+                error,
+            })
+    }
+
+    /// Skips the padding that aligns the operands of a switch to a multiple
+    /// of four bytes from the start of the code.
+    fn align(cursor: &mut Cursor<'_>) -> Result<(), BytecodeError> {
+        while cursor.position % 4 != 0 {
+            cursor.u8()?;
+        }
+        Ok(())
+    }
+
+    fn tableswitch(
+        &self,
+        pc: usize,
+        cursor: &mut Cursor<'_>,
+    ) -> Result<Instruction, BytecodeError> {
+        Self::align(cursor)?;
+        let default = cursor.u32()? as i32;
+        let low = cursor.u32()? as i32;
+        let high = cursor.u32()? as i32;
+        if low > high {
+            return Err(BytecodeError::InvalidOperand { pc: pc as u16 });
+        }
+        let count = (high as i64 - low as i64 + 1) as usize;
+        if cursor.remaining() / 4 < count {
+            return Err(cursor.truncated());
+        }
+        let mut offsets: Vec<i32> = Vec::with_capacity(count);
+        for _ in 0..count {
+            offsets.push(cursor.u32()? as i32);
+        }
+        Ok(Instruction::Tableswitch {
+            default,
+            low,
+            high,
+            offsets,
+        })
+    }
+
+    fn lookupswitch(
+        &self,
+        pc: usize,
+        cursor: &mut Cursor<'_>,
+    ) -> Result<Instruction, BytecodeError> {
+        Self::align(cursor)?;
+        let default = cursor.u32()? as i32;
+        let npairs = cursor.u32()? as i32;
+        if npairs < 0 {
+            return Err(BytecodeError::InvalidOperand { pc: pc as u16 });
+        }
+        let count = npairs as usize;
+        if cursor.remaining() / 8 < count {
+            return Err(cursor.truncated());
+        }
+        let mut pairs: Vec<(i32, i32)> = Vec::with_capacity(count);
+        for _ in 0..count {
+            let key = cursor.u32()? as i32;
+            let offset = cursor.u32()? as i32;
+            // The keys have to be sorted so the table can be searched.
+            if pairs.last().is_some_and(|&(last, _)| last >= key) {
+                return Err(BytecodeError::InvalidOperand { pc: pc as u16 });
+            }
+            pairs.push((key, offset));
+        }
+        Ok(Instruction::Lookupswitch { default, pairs })
+    }
+
+    fn invokeinterface(
+        &self,
+        pc: usize,
+        cursor: &mut Cursor<'_>,
+    ) -> Result<Instruction, BytecodeError> {
+        let index = self.constant(pc, cursor.u16()?, Operand::InterfaceMethod)?;
+        let count = cursor.u8()?;
+        if count == 0 || cursor.u8()? != 0 {
+            return Err(BytecodeError::InvalidOperand { pc: pc as u16 });
+        }
+        Ok(Instruction::Invokeinterface(index, count))
+    }
+
+    fn invokedynamic(
+        &self,
+        pc: usize,
+        cursor: &mut Cursor<'_>,
+    ) -> Result<Instruction, BytecodeError> {
+        let index = self.constant(pc, cursor.u16()?, Operand::InvokeDynamic)?;
+        if cursor.u16()? != 0 {
+            return Err(BytecodeError::InvalidOperand { pc: pc as u16 });
+        }
+        Ok(Instruction::Invokedynamic(index))
+    }
+
+    fn newarray(&self, pc: usize, cursor: &mut Cursor<'_>) -> Result<Instruction, BytecodeError> {
+        ArrayType::from_u8(cursor.u8()?)
+            .map(Instruction::Newarray)
+            .ok_or(BytecodeError::InvalidOperand { pc: pc as u16 })
+    }
+
+    fn multianewarray(
+        &self,
+        pc: usize,
+        cursor: &mut Cursor<'_>,
+    ) -> Result<Instruction, BytecodeError> {
+        let index = self.constant(pc, cursor.u16()?, Operand::Class)?;
+        let dimensions = cursor.u8()?;
+        if dimensions == 0 {
+            return Err(BytecodeError::InvalidOperand { pc: pc as u16 });
+        }
+        Ok(Instruction::Multianewarray(index, dimensions))
+    }
+
+    fn wide(&self, pc: usize, cursor: &mut Cursor<'_>) -> Result<Instruction, BytecodeError> {
+        let opcode = cursor.u8()?;
+        let wide = match opcode {
+            0x84 => Wide::Iinc(cursor.u16()?, cursor.u16()? as i16),
+            _ => {
+                let index = cursor.u16()?;
+                match opcode {
+                    0x15 => Wide::Iload(index),
+                    0x16 => Wide::Lload(index),
+                    0x17 => Wide::Fload(index),
+                    0x18 => Wide::Dload(index),
+                    0x19 => Wide::Aload(index),
+                    0x36 => Wide::Istore(index),
+                    0x37 => Wide::Lstore(index),
+                    0x38 => Wide::Fstore(index),
+                    0x39 => Wide::Dstore(index),
+                    0x3a => Wide::Astore(index),
+                    0xa9 => Wide::Ret(index),
+                    _ => return Err(BytecodeError::InvalidOperand { pc: pc as u16 }),
+                }
+            }
+        };
+        Ok(Instruction::Wide(wide))
+    }
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = Result<(u16, Instruction), BytecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pc = self.pc;
+        if self.failed || pc >= self.code.len() || pc > u16::MAX as usize {
+            return None;
+        }
+        match self.decode(pc) {
+            Ok((instruction, next)) => {
+                self.pc = next;
+                Some(Ok((pc as u16, instruction)))
+            }
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ClassBuilder;
+
+    /// A constant pool with an entry of each kind the tests refer to.
+    struct Pool {
+        constant_pool: ConstantPool,
+        integer: u16,
+        long: u16,
+        double: u16,
+        string: u16,
+        class: u16,
+        field: u16,
+        method: u16,
+        interface_method: u16,
+    }
+
+    fn pool() -> Pool {
+        let mut class = ClassBuilder::new(52);
+        let integer = class.integer(7);
+        let long = class.long(7);
+        let double = class.double(7.0);
+        let string = class.string("seven");
+        let list = class.class("java/util/List");
+        let field = class.member_ref(9, "Test", "count", "I");
+        let method = class.member_ref(10, "Test", "run", "()V");
+        let interface_method = class.member_ref(11, "java/util/List", "size", "()I");
+        Pool {
+            constant_pool: class.constant_pool(),
+            integer,
+            long,
+            double,
+            string,
+            class: list,
+            field,
+            method,
+            interface_method,
+        }
+    }
+
+    /// Decodes `code` as part of a version 52 class file, which is the
+    /// version of the pool the tests use.
+    fn decode(
+        code: &[u8],
+        constant_pool: &ConstantPool,
+    ) -> Result<Vec<(u16, Instruction)>, BytecodeError> {
+        Instructions::new(code, constant_pool, ClassFileVersion::new(52, 0)).collect()
+    }
+
+    /// The error decoding `code` stops at.
+    fn error(code: &[u8], constant_pool: &ConstantPool) -> BytecodeError {
+        decode(code, constant_pool).unwrap_err()
+    }
+
+    /// `count` nops followed by the switch `opcode`, the padding that aligns
+    /// its operands and then the operands themselves.
+    fn switch_at(count: usize, opcode: u8, operands: &[i32]) -> Vec<u8> {
+        let mut code = vec![0x00; count];
+        code.push(opcode);
+        while code.len() % 4 != 0 {
+            code.push(0x00);
+        }
+        for operand in operands {
+            code.extend_from_slice(&operand.to_be_bytes());
+        }
+        code
+    }
+
+    #[test]
+    fn aligns_switches_at_every_pc() {
+        let Pool { constant_pool, .. } = pool();
+        let switches = [
+            (
+                0xAA,
+                vec![20, -1, 0, 12, 16],
+                Instruction::Tableswitch {
+                    default: 20,
+                    low: -1,
+                    high: 0,
+                    offsets: vec![12, 16],
+                },
+            ),
+            (
+                0xAB,
+                vec![20, 2, -5, 12, 3, 16],
+                Instruction::Lookupswitch {
+                    default: 20,
+                    pairs: vec![(-5, 12), (3, 16)],
+                },
+            ),
+        ];
+        for (opcode, operands, switch) in switches {
+            for count in 0..4 {
+                let mut code = switch_at(count, opcode, &operands);
+                // Three bytes of padding at pc 0, down to none at pc 3.
+                assert_eq!(code.len(), 4 + 4 * operands.len(), "{count}");
+                code.push(0xB1);
+                let mut expected: Vec<(u16, Instruction)> =
+                    (0..count).map(|pc| (pc as u16, Instruction::Nop)).collect();
+                expected.push((count as u16, switch.clone()));
+                expected.push((code.len() as u16 - 1, Instruction::Return));
+                assert_eq!(decode(&code, &constant_pool), Ok(expected), "{count}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_switches() {
+        let Pool { constant_pool, .. } = pool();
+        // low > high
+        let code = switch_at(1, 0xAA, &[0, 1, 0]);
+        assert_eq!(
+            error(&code, &constant_pool),
+            BytecodeError::InvalidOperand { pc: 1 }
+        );
+        // Keys out of order, and the same key twice.
+        for keys in [[3, -5], [3, 3]] {
+            let code = switch_at(2, 0xAB, &[0, 2, keys[0], 0, keys[1], 0]);
+            assert_eq!(
+                error(&code, &constant_pool),
+                BytecodeError::InvalidOperand { pc: 2 },
+                "{keys:?}"
+            );
+        }
+        // A negative number of pairs.
+        let code = switch_at(0, 0xAB, &[0, -1]);
+        assert_eq!(
+            error(&code, &constant_pool),
+            BytecodeError::InvalidOperand { pc: 0 }
+        );
+    }
+
+    #[test]
+    fn decodes_every_wide_form() {
+        let Pool { constant_pool, .. } = pool();
+        let forms = [
+            (0x15, Wide::Iload(0x1234)),
+            (0x16, Wide::Lload(0x1234)),
+            (0x17, Wide::Fload(0x1234)),
+            (0x18, Wide::Dload(0x1234)),
+            (0x19, Wide::Aload(0x1234)),
+            (0x36, Wide::Istore(0x1234)),
+            (0x37, Wide::Lstore(0x1234)),
+            (0x38, Wide::Fstore(0x1234)),
+            (0x39, Wide::Dstore(0x1234)),
+            (0x3A, Wide::Astore(0x1234)),
+            (0xA9, Wide::Ret(0x1234)),
+        ];
+        for (opcode, wide) in forms {
+            assert_eq!(wide.opcode(), opcode);
+            let code = [0xC4, opcode, 0x12, 0x34, 0xB1];
+            assert_eq!(
+                decode(&code, &constant_pool),
+                Ok(vec![(0, Instruction::Wide(wide)), (4, Instruction::Return)]),
+                "{}",
+                wide.mnemonic()
+            );
+        }
+        let code = [0xC4, 0x84, 0x12, 0x34, 0xFF, 0xFE, 0xB1];
+        assert_eq!(
+            decode(&code, &constant_pool),
+            Ok(vec![
+                (0, Instruction::Wide(Wide::Iinc(0x1234, -2))),
+                (6, Instruction::Return)
+            ])
+        );
+        // Neither an instruction that takes no local nor an unknown opcode
+        // can be widened.
+        for opcode in [0x00, 0x10, 0xC4, 0xFF] {
+            assert_eq!(
+                error(&[0x00, 0xC4, opcode, 0, 0, 0, 0], &constant_pool),
+                BytecodeError::InvalidOperand { pc: 1 },
+                "{opcode:#04x}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_operands_cut_off_by_the_end_of_the_code() {
+        let Pool { constant_pool, .. } = pool();
+        let cut_off: [&[u8]; 9] = [
+            &[0x10],
+            &[0x00, 0x11, 0x00],
+            &[0x00, 0xA7, 0x00],
+            &[0x00, 0xC8, 0x00, 0x00, 0x00],
+            &[0x00, 0xC4, 0x84, 0x00, 0x01, 0x00],
+            &[0x00, 0xC4],
+            // In the padding of a switch, and in its operands.
+            &[0x00, 0xAA, 0x00],
+            &switch_at(1, 0xAA, &[0, 0, 1, 8])[..],
+            &switch_at(1, 0xAB, &[0, 2, 1, 8])[..],
+        ];
+        for code in cut_off {
+            let pc = if code[0] == 0x00 { 1 } else { 0 };
+            assert_eq!(
+                error(code, &constant_pool),
+                BytecodeError::Truncated { pc },
+                "{code:02x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn checks_the_kind_of_constant_operands() {
+        let Pool {
+            constant_pool,
+            integer,
+            long,
+            double,
+            string,
+            class,
+            field,
+            method,
+            interface_method,
+        } = pool();
+        let [
+            integer,
+            long,
+            double,
+            string,
+            class,
+            field,
+            method,
+            interface_method,
+        ] = [
+            integer,
+            long,
+            double,
+            string,
+            class,
+            field,
+            method,
+            interface_method,
+        ]
+        .map(u16::to_be_bytes);
+        let loads = [
+            [0x13, integer[0], integer[1]],
+            [0x13, string[0], string[1]],
+            [0x13, class[0], class[1]],
+            [0x14, long[0], long[1]],
+            [0x14, double[0], double[1]],
+            [0xB2, field[0], field[1]],
+            [0xB6, method[0], method[1]],
+            [0xB8, interface_method[0], interface_method[1]],
+            [0xBB, class[0], class[1]],
+        ];
+        for code in loads {
+            assert!(decode(&code, &constant_pool).is_ok(), "{code:02x?}");
+        }
+        let category_1 = "loadable constant of a category 1 type";
+        let category_2 = "Long, Double or Dynamic of type long or double";
+        let wrong_kind = [
+            (vec![0x12, long[1]], long, category_1),
+            (vec![0x13, double[0], double[1]], double, category_1),
+            (vec![0x13, field[0], field[1]], field, category_1),
+            (vec![0x14, integer[0], integer[1]], integer, category_2),
+            (vec![0x14, string[0], string[1]], string, category_2),
+            (vec![0xB4, method[0], method[1]], method, "Fieldref"),
+            (
+                vec![0xB6, interface_method[0], interface_method[1]],
+                interface_method,
+                "Methodref",
+            ),
+            (
+                vec![0xB9, method[0], method[1], 1, 0],
+                method,
+                "InterfaceMethodref",
+            ),
+            (vec![0xBB, string[0], string[1]], string, "Class"),
+        ];
+        for (code, index, expected) in wrong_kind {
+            assert_eq!(
+                error(&code, &constant_pool),
+                BytecodeError::Constant {
+                    pc: 0,
+                    error: ConstantPoolError::UnexpectedType {
+                        index: u16::from_be_bytes(index),
+                        expected,
+                    },
+                },
+                "{code:02x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_calls_interface_methods_directly_from_version_52() {
+        for major_version in [51, 52] {
+            let mut class = ClassBuilder::new(major_version);
+            let size = class.member_ref(11, "java/util/List", "size", "()I");
+            let [high, low] = size.to_be_bytes();
+            // invokestatic List.size, pop, return
+            let code = class.code(1, 0, &[0xB8, high, low, 0x57, 0xB1], &[]);
+            class.method(0x0009, "run", "()V", &[code]);
+            let class_file = class.parse();
+            let code = class_file.methods[0].code().unwrap();
+            let instructions: Result<Vec<_>, _> = code
+                .instructions(&class_file.constant_pool, class_file.version())
+                .collect();
+            if major_version == 51 {
+                assert_eq!(
+                    instructions,
+                    Err(BytecodeError::Constant {
+                        pc: 0,
+                        error: ConstantPoolError::UnexpectedType {
+                            index: size,
+                            expected: "Methodref",
+                        },
+                    })
+                );
+            } else {
+                assert_eq!(
+                    instructions.unwrap()[0],
+                    (0, Instruction::Invokestatic(size))
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::slice;
+use std::str::EncodeUtf16;
+use std::sync::OnceLock;
+
+/// The contents of a `CONSTANT_Utf8` entry: a sequence of UTF-16 code units,
+/// just like a `java.lang.String`.
+///
+/// Unlike a Rust string it can hold surrogates that are not part of a pair,
+/// which are legal in class files. Every code unit is kept, so writing the
+/// entry back gives the bytes it was read from. Converting to a `str` replaces
+/// unpaired surrogates with U+FFFD and only happens when asked for.
+#[derive(Clone, Default)]
+pub struct JavaString {
+    repr: Repr,
+}
+
+#[derive(Clone)]
+enum Repr {
+    /// Code units that are valid UTF-16, stored as the string they encode.
+    Unicode(String),
+    /// Code units with at least one unpaired surrogate, along with their
+    /// lossy conversion once it has been made.
+    Utf16 {
+        units: Vec<u16>,
+        lossy: OnceLock<String>,
+    },
+}
+
+impl Default for Repr {
+    fn default() -> Self {
+        Repr::Unicode(String::new())
+    }
+}
+
+impl JavaString {
+    /// Creates a string from its UTF-16 code units.
+    pub fn from_utf16(units: Vec<u16>) -> JavaString {
+        let repr = match String::from_utf16(&units) {
+            Ok(string) => Repr::Unicode(string),
+            Err(_) => Repr::Utf16 {
+                units,
+                lossy: OnceLock::new(),
+            },
+        };
+        JavaString { repr }
+    }
+
+    /// The string, when it does not hold unpaired surrogates.
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.repr {
+            Repr::Unicode(string) => Some(string),
+            Repr::Utf16 { .. } => None,
+        }
+    }
+
+    /// The string with every unpaired surrogate replaced by U+FFFD. The
+    /// conversion is made the first time it is needed and then kept.
+    pub fn to_str_lossy(&self) -> &str {
+        match &self.repr {
+            Repr::Unicode(string) => string,
+            Repr::Utf16 { units, lossy } => lossy.get_or_init(|| String::from_utf16_lossy(units)),
+        }
+    }
+
+    /// The UTF-16 code units of the string.
+    pub fn code_units(&self) -> impl Iterator<Item = u16> + '_ {
+        match &self.repr {
+            Repr::Unicode(string) => CodeUnits::Unicode(string.encode_utf16()),
+            Repr::Utf16 { units, .. } => CodeUnits::Utf16(units.iter()),
+        }
+    }
+
+    /// Decodes the string into characters, giving the unpaired surrogates as
+    /// errors.
+    pub fn chars(&self) -> impl Iterator<Item = Result<char, u16>> + '_ {
+        char::decode_utf16(self.code_units())
+            .map(|decoded| decoded.map_err(|error| error.unpaired_surrogate()))
+    }
+}
+
+/// The code units of either representation of a [`JavaString`].
+enum CodeUnits<'a> {
+    Unicode(EncodeUtf16<'a>),
+    Utf16(slice::Iter<'a, u16>),
+}
+
+impl Iterator for CodeUnits<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        match self {
+            CodeUnits::Unicode(units) => units.next(),
+            CodeUnits::Utf16(units) => units.next().copied(),
+        }
+    }
+}
+
+impl From<String> for JavaString {
+    fn from(string: String) -> Self {
+        JavaString {
+            repr: Repr::Unicode(string),
+        }
+    }
+}
+
+impl From<&str> for JavaString {
+    fn from(string: &str) -> Self {
+        JavaString::from(string.to_string())
+    }
+}
+
+// Both representations are only ever built from code units that fit them, so
+// strings with the same code units always have the same representation.
+impl PartialEq for JavaString {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.repr, &other.repr) {
+            (Repr::Unicode(left), Repr::Unicode(right)) => left == right,
+            (Repr::Utf16 { units: left, .. }, Repr::Utf16 { units: right, .. }) => left == right,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for JavaString {}
+
+impl PartialEq<str> for JavaString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+
+impl Hash for JavaString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.repr {
+            Repr::Unicode(string) => string.hash(state),
+            Repr::Utf16 { units, .. } => units.hash(state),
+        }
+    }
+}
+
+/// Shows the string like a `str`, with unpaired surrogates as `\u{dc00}`.
+impl fmt::Debug for JavaString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(string) = self.as_str() {
+            return fmt::Debug::fmt(string, f);
+        }
+        write!(f, "\"")?;
+        for decoded in self.chars() {
+            match decoded {
+                Ok(c) => write!(f, "{}", c.escape_debug())?,
+                Err(unit) => write!(f, "\\u{{{unit:x}}}")?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+/// Writes the lossy conversion of the string.
+impl fmt::Display for JavaString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_str_lossy())
+    }
+}
@@ -0,0 +1,785 @@
+use crate::constantpool::reference_kind_name;
+use crate::{
+    Attribute, AttributeInfo, ClassFile, ClassRef, Code, ConstantPool, ConstantPoolError,
+    ConstantPoolItem, FieldInfo, Instruction, JavaString, MethodInfo, Wide,
+};
+
+use std::fmt;
+
+/// A class file printed as a JSON document.
+///
+/// The document is an object with the members `minor_version` and
+/// `major_version` (numbers), `access_flags` (a flags object), `this_class`
+/// (an internal name such as `java/lang/String`), `super_class` (an internal
+/// name, or `null` for `java/lang/Object`), `interfaces` (an array of
+/// internal names), `constant_pool` (an array of constants), `fields` and
+/// `methods` (arrays of members) and `attributes` (an array of attributes).
+///
+/// A flags object has `value`, the flags as a number, and `names`, the javap
+/// names of the flags that are set, such as `"ACC_PUBLIC"`.
+///
+/// A constant has `index`, `tag` (the kind of entry as javap names it, such
+/// as `"Methodref"`) and `value`:
+///
+/// - `Integer`, `Long`, `Float`, `Double`: a number, or one of the strings
+///   `"NaN"`, `"Infinity"` and `"-Infinity"`.
+/// - `Utf8`, `String`: the string itself.
+/// - `Class`, `Module`, `Package`: the name.
+/// - `MethodType`: the descriptor.
+/// - `Fieldref`, `Methodref`, `InterfaceMethodref`: an object with `class`,
+///   `name` and `descriptor`.
+/// - `NameAndType`: an object with `name` and `descriptor`.
+/// - `MethodHandle`: an object with `reference_kind`, the javap name of the
+///   kind such as `"REF_invokeStatic"`, and the `class`, `name` and
+///   `descriptor` of the member it refers to.
+/// - `InvokeDynamic`, `Dynamic`: an object with `bootstrap_method`, the
+///   index into the BootstrapMethods attribute, `name` and `descriptor`.
+///
+/// Constants that refer to other entries also have `references`, an array
+/// of the indices they refer to. The unusable entries after a `Long` or
+/// `Double` are left out.
+///
+/// Strings from the constant pool are written exactly, so an unpaired
+/// surrogate becomes an escape such as `"\ud800"`.
+///
+/// A field has `name`, `descriptor`, `type` (the Java type, such as
+/// `java.lang.String[]`), `access_flags` and `attributes`. A method has
+/// `name`, `descriptor`, `parameters` (an array of Java types), `return` (a
+/// Java type or `"void"`), `access_flags` and `attributes`.
+///
+/// Every attribute has a `name`. The attributes this crate decodes have these
+/// other members, and every other attribute has `bytes`, its contents as a
+/// hexadecimal string.
+///
+/// - `ConstantValue`: `value`, the constant as a string like `"int 1"`.
+/// - `Code`: `max_stack`, `max_locals`, `code` (the bytecode as a
+///   hexadecimal string), `instructions`, `exception_table` and
+///   `attributes`. Each instruction has `pc`, `mnemonic` and `operands`, an
+///   array of numbers in the order they are encoded, leaving out the padding
+///   of the switches. Branch targets are relative to the pc of the
+///   instruction. A `wide` instruction has the opcode it widens as its first
+///   operand. Decoding stops at the first invalid instruction, which is
+///   given as `pc` and `error` instead. Each exception handler has
+///   `start_pc`, `end_pc`, `handler_pc` and `catch_type`, an internal name or
+///   `null` for a handler that catches everything.
+/// - `Exceptions`, `NestMembers`, `PermittedSubclasses`: `classes`, an array
+///   of internal names.
+/// - `InnerClasses`: `classes`, an array of objects with `inner_class`,
+///   `outer_class` (an internal name or `null`), `inner_name` (a string or
+///   `null`) and `access_flags`.
+/// - `EnclosingMethod`: `class` and `method`, the name and descriptor of the
+///   method as `name:descriptor` or `null`.
+/// - `Signature`: `signature`.
+/// - `SourceFile`: `source_file`.
+/// - `LineNumberTable`: `lines`, an array of objects with `start_pc` and
+///   `line_number`.
+/// - `LocalVariableTable`, `LocalVariableTypeTable`: `variables`, an array of
+///   objects with `start_pc`, `length`, `name`, `descriptor` (a signature in
+///   the LocalVariableTypeTable) and `index`.
+/// - `BootstrapMethods`: `methods`, an array of objects with
+///   `method_handle` and `arguments`, the resolved strings of the constants.
+/// - `MethodParameters`: `parameters`, an array of objects with `name` (a
+///   string or `null`) and `access_flags`.
+/// - `NestHost`: `class`.
+/// - `Synthetic`, `Deprecated`: no other members.
+pub struct Json<'a> {
+    class_file: &'a ClassFile,
+}
+
+impl ClassFile {
+    /// Returns a value that prints the class file as JSON.
+    pub fn to_json(&self) -> Json<'_> {
+        Json { class_file: self }
+    }
+}
+
+impl fmt::Display for Json<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class_file = self.class_file;
+        let constant_pool = &class_file.constant_pool;
+        let super_class = match class_file.super_class {
+            0 => Value::Null,
+            index => self.class_name(index),
+        };
+        let document = Value::Object(vec![
+            ("minor_version", number(class_file.minor_version)),
+            ("major_version", number(class_file.major_version)),
+            (
+                "access_flags",
+                flags(class_file.access_flags, class_file.flags().names()),
+            ),
+            ("this_class", self.class_name(class_file.this_class)),
+            ("super_class", super_class),
+            (
+                "interfaces",
+                Value::Array(class_file.interfaces.iter().map(class_ref).collect()),
+            ),
+            (
+                "constant_pool",
+                Value::Array(
+                    constant_pool
+                        .iter()
+                        .map(|(index, item)| self.constant(index, item))
+                        .collect(),
+                ),
+            ),
+            (
+                "fields",
+                Value::Array(
+                    class_file
+                        .fields
+                        .iter()
+                        .map(|field| self.field(field))
+                        .collect(),
+                ),
+            ),
+            (
+                "methods",
+                Value::Array(
+                    class_file
+                        .methods
+                        .iter()
+                        .map(|method| self.method(method))
+                        .collect(),
+                ),
+            ),
+            ("attributes", self.attributes(&class_file.attributes)),
+        ]);
+        document.write(f, 0)?;
+        writeln!(f)
+    }
+}
+
+impl Json<'_> {
+    fn constant_pool(&self) -> &ConstantPool {
+        &self.class_file.constant_pool
+    }
+
+    fn resolve(&self, index: u16) -> Value {
+        string(
+            self.constant_pool()
+                .resolve(index)
+                .unwrap_or_else(|error| format!("<{error}>")),
+        )
+    }
+
+    fn utf8(&self, index: u16) -> Value {
+        match self.constant_pool().java_string(index) {
+            Ok(data) => Value::String(data.clone()),
+            Err(error) => invalid(error),
+        }
+    }
+
+    fn optional_utf8(&self, index: u16) -> Value {
+        match index {
+            0 => Value::Null,
+            index => self.utf8(index),
+        }
+    }
+
+    fn class_name(&self, index: u16) -> Value {
+        match self.constant_pool().get(index) {
+            Ok(ConstantPoolItem::Class(name_index)) => self.utf8(*name_index),
+            Ok(_) => invalid(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "Class",
+            }),
+            Err(error) => invalid(error),
+        }
+    }
+
+    /// The `name` and `descriptor` members for the `CONSTANT_NameAndType`
+    /// entry at `index`.
+    fn name_and_type(&self, index: u16) -> Vec<(&'static str, Value)> {
+        let (name, descriptor) = match self.constant_pool().get(index) {
+            Ok(ConstantPoolItem::NameAndType(name_index, descriptor_index)) => {
+                (self.utf8(*name_index), self.utf8(*descriptor_index))
+            }
+            Ok(_) => {
+                let error = ConstantPoolError::UnexpectedType {
+                    index,
+                    expected: "NameAndType",
+                };
+                (invalid(error.clone()), invalid(error))
+            }
+            Err(error) => (invalid(error.clone()), invalid(error)),
+        };
+        vec![("name", name), ("descriptor", descriptor)]
+    }
+
+    /// The `class`, `name` and `descriptor` members for the field or method
+    /// reference at `index`.
+    fn member_ref(&self, index: u16) -> Vec<(&'static str, Value)> {
+        match self.constant_pool().get(index) {
+            Ok(
+                ConstantPoolItem::Fieldref(class_index, name_and_type_index)
+                | ConstantPoolItem::Methodref(class_index, name_and_type_index)
+                | ConstantPoolItem::InterfaceMethodref(class_index, name_and_type_index),
+            ) => {
+                let mut members = vec![("class", self.class_name(*class_index))];
+                members.extend(self.name_and_type(*name_and_type_index));
+                members
+            }
+            Ok(_) => vec![(
+                "class",
+                invalid(ConstantPoolError::UnexpectedType {
+                    index,
+                    expected: "Fieldref, Methodref or InterfaceMethodref",
+                }),
+            )],
+            Err(error) => vec![("class", invalid(error))],
+        }
+    }
+
+    fn constant(&self, index: u16, item: &ConstantPoolItem) -> Value {
+        let (value, references) = match item {
+            ConstantPoolItem::Utf8(data) => (Value::String(data.clone()), vec![]),
+            ConstantPoolItem::Integer(value) => (number(value), vec![]),
+            ConstantPoolItem::Long(value) => (number(value), vec![]),
+            ConstantPoolItem::Float(value) => (float(*value as f64, format!("{value:?}")), vec![]),
+            ConstantPoolItem::Double(value) => (float(*value, format!("{value:?}")), vec![]),
+            ConstantPoolItem::Class(reference)
+            | ConstantPoolItem::String(reference)
+            | ConstantPoolItem::MethodType(reference)
+            | ConstantPoolItem::Module(reference)
+            | ConstantPoolItem::Package(reference) => (self.utf8(*reference), vec![*reference]),
+            ConstantPoolItem::Fieldref(class_index, name_and_type_index)
+            | ConstantPoolItem::Methodref(class_index, name_and_type_index)
+            | ConstantPoolItem::InterfaceMethodref(class_index, name_and_type_index) => (
+                Value::Object(self.member_ref(index)),
+                vec![*class_index, *name_and_type_index],
+            ),
+            ConstantPoolItem::NameAndType(name_index, descriptor_index) => (
+                Value::Object(self.name_and_type(index)),
+                vec![*name_index, *descriptor_index],
+            ),
+            // The bootstrap method is an index into the BootstrapMethods
+            // attribute, so only the NameAndType counts as a reference.
+            ConstantPoolItem::InvokeDynamic(bootstrap, name_and_type_index)
+            | ConstantPoolItem::Dynamic(bootstrap, name_and_type_index) => {
+                let mut members = vec![("bootstrap_method", number(bootstrap))];
+                members.extend(self.name_and_type(*name_and_type_index));
+                (Value::Object(members), vec![*name_and_type_index])
+            }
+            // The reference kind is not an index, so only the member counts.
+            ConstantPoolItem::MethodHandle(kind, reference) => {
+                let mut members = vec![("reference_kind", string(reference_kind_name(*kind)))];
+                members.extend(self.member_ref(*reference));
+                (Value::Object(members), vec![*reference])
+            }
+        };
+        let mut members = vec![
+            ("index", number(index)),
+            ("tag", string(item.kind())),
+            ("value", value),
+        ];
+        if !references.is_empty() {
+            members.push((
+                "references",
+                Value::Array(references.into_iter().map(number).collect()),
+            ));
+        }
+        Value::Object(members)
+    }
+
+    fn field(&self, field: &FieldInfo) -> Value {
+        Value::Object(vec![
+            ("name", Value::String(field.name.clone())),
+            ("descriptor", self.utf8(field.descriptor_index)),
+            ("type", string(field.descriptor.to_string())),
+            (
+                "access_flags",
+                flags(field.access_flags, field.flags().names()),
+            ),
+            ("attributes", self.attributes(&field.attributes)),
+        ])
+    }
+
+    fn method(&self, method: &MethodInfo) -> Value {
+        let ret = match &method.descriptor.ret {
+            Some(ret) => ret.to_string(),
+            None => "void".to_string(),
+        };
+        Value::Object(vec![
+            ("name", Value::String(method.name.clone())),
+            ("descriptor", self.utf8(method.descriptor_index)),
+            (
+                "parameters",
+                Value::Array(
+                    method
+                        .descriptor
+                        .params
+                        .iter()
+                        .map(|param| string(param.to_string()))
+                        .collect(),
+                ),
+            ),
+            ("return", string(ret)),
+            (
+                "access_flags",
+                flags(method.access_flags, method.flags().names()),
+            ),
+            ("attributes", self.attributes(&method.attributes)),
+        ])
+    }
+
+    fn attributes(&self, attributes: &[AttributeInfo]) -> Value {
+        Value::Array(
+            attributes
+                .iter()
+                .map(|info| self.attribute(&info.attribute))
+                .collect(),
+        )
+    }
+
+    fn attribute(&self, attribute: &Attribute) -> Value {
+        let mut members = vec![("name", string(attribute.name()))];
+        match attribute {
+            Attribute::ConstantValue(index) => members.push(("value", self.resolve(*index))),
+            Attribute::Code(code) => members.extend(self.code(code)),
+            Attribute::Exceptions(classes)
+            | Attribute::NestMembers(classes)
+            | Attribute::PermittedSubclasses(classes) => members.push((
+                "classes",
+                Value::Array(classes.iter().map(class_ref).collect()),
+            )),
+            Attribute::InnerClasses(inner_classes) => members.push((
+                "classes",
+                Value::Array(
+                    inner_classes
+                        .iter()
+                        .map(|inner| {
+                            let outer_class = match inner.outer_class_info_index {
+                                0 => Value::Null,
+                                index => self.class_name(index),
+                            };
+                            Value::Object(vec![
+                                ("inner_class", class_ref(&inner.inner_class)),
+                                ("outer_class", outer_class),
+                                ("inner_name", self.optional_utf8(inner.inner_name_index)),
+                                (
+                                    "access_flags",
+                                    flags(inner.inner_class_access_flags, inner.flags().names()),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            )),
+            Attribute::EnclosingMethod {
+                class,
+                method_index,
+            } => {
+                members.push(("class", class_ref(class)));
+                members.push((
+                    "method",
+                    match method_index {
+                        0 => Value::Null,
+                        index => self.resolve(*index),
+                    },
+                ));
+            }
+            Attribute::Synthetic | Attribute::Deprecated => {}
+            Attribute::Signature(index) => members.push(("signature", self.utf8(*index))),
+            Attribute::SourceFile(index) => members.push(("source_file", self.utf8(*index))),
+            Attribute::LineNumberTable(lines) => members.push((
+                "lines",
+                Value::Array(
+                    lines
+                        .iter()
+                        .map(|line| {
+                            Value::Object(vec![
+                                ("start_pc", number(line.start_pc)),
+                                ("line_number", number(line.line_number)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            )),
+            Attribute::LocalVariableTable(variables)
+            | Attribute::LocalVariableTypeTable(variables) => members.push((
+                "variables",
+                Value::Array(
+                    variables
+                        .iter()
+                        .map(|variable| {
+                            Value::Object(vec![
+                                ("start_pc", number(variable.start_pc)),
+                                ("length", number(variable.length)),
+                                ("name", self.utf8(variable.name_index)),
+                                ("descriptor", self.utf8(variable.descriptor_index)),
+                                ("index", number(variable.index)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            )),
+            Attribute::BootstrapMethods(methods) => members.push((
+                "methods",
+                Value::Array(
+                    methods
+                        .iter()
+                        .map(|method| {
+                            Value::Object(vec![
+                                ("method_handle", self.resolve(method.method_ref)),
+                                (
+                                    "arguments",
+                                    Value::Array(
+                                        method
+                                            .arguments
+                                            .iter()
+                                            .map(|argument| self.resolve(*argument))
+                                            .collect(),
+                                    ),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            )),
+            Attribute::MethodParameters(parameters) => members.push((
+                "parameters",
+                Value::Array(
+                    parameters
+                        .iter()
+                        .map(|parameter| {
+                            Value::Object(vec![
+                                ("name", self.optional_utf8(parameter.name_index)),
+                                (
+                                    "access_flags",
+                                    flags(parameter.access_flags, parameter.flags().names()),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            )),
+            Attribute::NestHost(class) => members.push(("class", class_ref(class))),
+            Attribute::Raw { bytes, .. } => members.push(("bytes", string(hex(bytes)))),
+        }
+        Value::Object(members)
+    }
+
+    fn code(&self, code: &Code) -> Vec<(&'static str, Value)> {
+        let mut instructions: Vec<Value> = Vec::new();
+        for result in code.instructions(self.constant_pool(), self.class_file.version()) {
+            instructions.push(match result {
+                Ok((pc, instruction)) => Value::Object(vec![
+                    ("pc", number(pc)),
+                    ("mnemonic", string(instruction.mnemonic())),
+                    (
+                        "operands",
+                        Value::Array(operands(&instruction).into_iter().map(number).collect()),
+                    ),
+                ]),
+                Err(error) => Value::Object(vec![
+                    ("pc", number(error.pc())),
+                    ("error", string(error.to_string())),
+                ]),
+            });
+        }
+        vec![
+            ("max_stack", number(code.max_stack)),
+            ("max_locals", number(code.max_locals)),
+            ("code", string(hex(&code.code))),
+            ("instructions", Value::Array(instructions)),
+            (
+                "exception_table",
+                Value::Array(
+                    code.exception_table
+                        .iter()
+                        .map(|handler| {
+                            Value::Object(vec![
+                                ("start_pc", number(handler.start_pc)),
+                                ("end_pc", number(handler.end_pc)),
+                                ("handler_pc", number(handler.handler_pc)),
+                                (
+                                    "catch_type",
+                                    handler.catch_type.as_ref().map_or(Value::Null, class_ref),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("attributes", self.attributes(&code.attributes)),
+        ]
+    }
+}
+
+/// The operands of an instruction in the order they are encoded.
+fn operands(instruction: &Instruction) -> Vec<i64> {
+    match instruction {
+        Instruction::Bipush(value) => vec![*value as i64],
+        Instruction::Sipush(value) => vec![*value as i64],
+        Instruction::Ldc(index)
+        | Instruction::Iload(index)
+        | Instruction::Lload(index)
+        | Instruction::Fload(index)
+        | Instruction::Dload(index)
+        | Instruction::Aload(index)
+        | Instruction::Istore(index)
+        | Instruction::Lstore(index)
+        | Instruction::Fstore(index)
+        | Instruction::Dstore(index)
+        | Instruction::Astore(index)
+        | Instruction::Ret(index) => vec![*index as i64],
+        Instruction::LdcW(index)
+        | Instruction::Ldc2W(index)
+        | Instruction::Getstatic(index)
+        | Instruction::Putstatic(index)
+        | Instruction::Getfield(index)
+        | Instruction::Putfield(index)
+        | Instruction::Invokevirtual(index)
+        | Instruction::Invokespecial(index)
+        | Instruction::Invokestatic(index)
+        | Instruction::Invokedynamic(index)
+        | Instruction::New(index)
+        | Instruction::Anewarray(index)
+        | Instruction::Checkcast(index)
+        | Instruction::Instanceof(index) => vec![*index as i64],
+        Instruction::Iinc(index, value) => vec![*index as i64, *value as i64],
+        Instruction::Ifeq(offset)
+        | Instruction::Ifne(offset)
+        | Instruction::Iflt(offset)
+        | Instruction::Ifge(offset)
+        | Instruction::Ifgt(offset)
+        | Instruction::Ifle(offset)
+        | Instruction::IfIcmpeq(offset)
+        | Instruction::IfIcmpne(offset)
+        | Instruction::IfIcmplt(offset)
+        | Instruction::IfIcmpge(offset)
+        | Instruction::IfIcmpgt(offset)
+        | Instruction::IfIcmple(offset)
+        | Instruction::IfAcmpeq(offset)
+        | Instruction::IfAcmpne(offset)
+        | Instruction::Goto(offset)
+        | Instruction::Jsr(offset)
+        | Instruction::Ifnull(offset)
+        | Instruction::Ifnonnull(offset) => vec![*offset as i64],
+        Instruction::GotoW(offset) | Instruction::JsrW(offset) => vec![*offset as i64],
+        Instruction::Tableswitch {
+            default,
+            low,
+            high,
+            offsets,
+        } => [*default, *low, *high]
+            .iter()
+            .chain(offsets)
+            .map(|&value| value as i64)
+            .collect(),
+        Instruction::Lookupswitch { default, pairs } => {
+            let mut operands = vec![*default as i64, pairs.len() as i64];
+            for (key, offset) in pairs {
+                operands.push(*key as i64);
+                operands.push(*offset as i64);
+            }
+            operands
+        }
+        Instruction::Invokeinterface(index, count) => vec![*index as i64, *count as i64, 0],
+        Instruction::Newarray(array_type) => vec![*array_type as u8 as i64],
+        Instruction::Multianewarray(index, dimensions) => vec![*index as i64, *dimensions as i64],
+        Instruction::Wide(wide) => {
+            let opcode = wide.opcode() as i64;
+            match wide {
+                Wide::Iinc(index, value) => vec![opcode, *index as i64, *value as i64],
+                Wide::Iload(index)
+                | Wide::Lload(index)
+                | Wide::Fload(index)
+                | Wide::Dload(index)
+                | Wide::Aload(index)
+                | Wide::Istore(index)
+                | Wide::Lstore(index)
+                | Wide::Fstore(index)
+                | Wide::Dstore(index)
+                | Wide::Astore(index)
+                | Wide::Ret(index) => vec![opcode, *index as i64],
+            }
+        }
+        _ => vec![],
+    }
+}
+
+/// A JSON value. Objects keep their members in the order they were added.
+enum Value {
+    Null,
+    Number(String),
+    String(JavaString),
+    Array(Vec<Value>),
+    Object(Vec<(&'static str, Value)>),
+}
+
+fn number(value: impl ToString) -> Value {
+    Value::Number(value.to_string())
+}
+
+fn string(value: impl Into<JavaString>) -> Value {
+    Value::String(value.into())
+}
+
+/// Stands in for a constant that can not be resolved.
+fn invalid(error: ConstantPoolError) -> Value {
+    string(format!("<{error}>"))
+}
+
+/// A floating point number, which JSON can only hold when it is finite.
+/// `formatted` is the shortest representation of the value.
+fn float(value: f64, formatted: String) -> Value {
+    if value.is_nan() {
+        string("NaN")
+    } else if value.is_infinite() {
+        string(if value > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        Value::Number(formatted)
+    }
+}
+
+fn class_ref(class: &ClassRef) -> Value {
+    string(class.name.as_str())
+}
+
+fn flags<'a>(access_flags: u16, names: impl Iterator<Item = &'a str>) -> Value {
+    Value::Object(vec![
+        ("value", number(access_flags)),
+        ("names", Value::Array(names.map(string).collect())),
+    ])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+impl Value {
+    fn is_scalar(&self) -> bool {
+        !matches!(self, Value::Array(_) | Value::Object(_))
+    }
+
+    /// Writes the value at the given depth of indentation. Arrays that only
+    /// hold scalars are kept on one line.
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Number(value) => f.write_str(value),
+            Value::String(value) => write_string(f, value.chars()),
+            Value::Array(values) if values.is_empty() => f.write_str("[]"),
+            Value::Array(values) if values.iter().all(Value::is_scalar) => {
+                f.write_str("[")?;
+                for (position, value) in values.iter().enumerate() {
+                    if position > 0 {
+                        f.write_str(", ")?;
+                    }
+                    value.write(f, depth)?;
+                }
+                f.write_str("]")
+            }
+            Value::Array(values) => {
+                f.write_str("[\n")?;
+                for (position, value) in values.iter().enumerate() {
+                    if position > 0 {
+                        f.write_str(",\n")?;
+                    }
+                    write!(f, "{:indent$}", "", indent = (depth + 1) * 2)?;
+                    value.write(f, depth + 1)?;
+                }
+                write!(f, "\n{:indent$}]", "", indent = depth * 2)
+            }
+            Value::Object(members) if members.is_empty() => f.write_str("{}"),
+            Value::Object(members) => {
+                f.write_str("{\n")?;
+                for (position, (name, value)) in members.iter().enumerate() {
+                    if position > 0 {
+                        f.write_str(",\n")?;
+                    }
+                    write!(f, "{:indent$}", "", indent = (depth + 1) * 2)?;
+                    write_string(f, name.chars().map(Ok))?;
+                    f.write_str(": ")?;
+                    value.write(f, depth + 1)?;
+                }
+                write!(f, "\n{:indent$}}}", "", indent = depth * 2)
+            }
+        }
+    }
+}
+
+/// Writes a JSON string literal, escaping what RFC 8259 requires. The
+/// characters come from [`JavaString::chars`], so unpaired surrogates are
+/// written as `\u` escapes, which JSON allows.
+fn write_string(
+    f: &mut fmt::Formatter<'_>,
+    chars: impl Iterator<Item = Result<char, u16>>,
+) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in chars {
+        match c {
+            Ok('"') => f.write_str("\\\"")?,
+            Ok('\\') => f.write_str("\\\\")?,
+            Ok('\n') => f.write_str("\\n")?,
+            Ok('\r') => f.write_str("\\r")?,
+            Ok('\t') => f.write_str("\\t")?,
+            Ok(c) if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            Ok(c) => write!(f, "{c}")?,
+            Err(surrogate) => write!(f, "\\u{surrogate:04x}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ClassBuilder;
+
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/Sample.class");
+
+    #[test]
+    fn serialises_the_sample_class() {
+        let json = ClassFile::parse(SAMPLE).unwrap().to_json().to_string();
+        for expected in [
+            "{\n  \"minor_version\": 0,\n  \"major_version\": 61,\n",
+            "\n  \"this_class\": \"Sample\",\n",
+            "\n  \"super_class\": \"java/lang/Object\",\n",
+            "\n  \"interfaces\": [\"java/lang/Runnable\", \"java/io/Serializable\"],\n",
+            "\"index\": 1,\n      \"tag\": \"Methodref\",\n      \"value\": {\n        \
+             \"class\": \"java/lang/Object\",\n        \"name\": \"<init>\",\n        \
+             \"descriptor\": \"()V\"\n      },\n      \"references\": [2, 3]\n",
+            "\"tag\": \"NameAndType\",\n      \"value\": {\n        \"name\": \"<init>\",\n        \
+             \"descriptor\": \"()V\"\n      },",
+            "\"tag\": \"InvokeDynamic\",\n      \"value\": {\n        \"bootstrap_method\": 0,\n        \
+             \"name\": \"run\",\n        \"descriptor\": \"(LSample;)Ljava/lang/Runnable;\"\n      },\n      \
+             \"references\": [17]\n",
+            "\"tag\": \"MethodHandle\",\n      \"value\": {\n        \
+             \"reference_kind\": \"REF_invokeStatic\",\n        \
+             \"class\": \"java/lang/invoke/LambdaMetafactory\",\n        \
+             \"name\": \"metafactory\",",
+            "\"tag\": \"Class\",\n      \"value\": \"[[I\",",
+            // The Utf8 entry and the String entry both hold the string itself.
+            "\"tag\": \"Utf8\",\n      \"value\": \"héllo\\u0000 😀 world\"\n",
+            "\"tag\": \"String\",\n      \"value\": \"héllo\\u0000 😀 world\",",
+            "\"name\": \"names\",\n      \"descriptor\": \"Ljava/util/List;\",\n      \"type\": \"java.util.List\",",
+            "\"name\": \"run\",\n      \"descriptor\": \"()V\",\n      \"parameters\": [],\n      \"return\": \"void\",",
+        ] {
+            assert!(json.contains(expected), "{expected}");
+        }
+        assert!(json.ends_with("\n  ]\n}\n"));
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let mut class = ClassBuilder::new(52);
+        let quoted = class.utf8("say \"hi\\\" \u{1}\t\n\r\u{1F}\u{7F}");
+        // "x" and an unpaired low surrogate.
+        let surrogate = class.utf8_bytes(&[0x78, 0xED, 0xB8, 0x80]);
+        let string = class.constant(8, &surrogate.to_be_bytes());
+        let json = class.parse().to_json().to_string();
+        assert!(json.contains(&format!(
+            "\"index\": {quoted},\n      \"tag\": \"Utf8\",\n      \
+             \"value\": \"say \\\"hi\\\\\\\" \\u0001\\t\\n\\r\\u001f\u{7F}\"\n"
+        )));
+        assert!(json.contains(&format!(
+            "\"index\": {surrogate},\n      \"tag\": \"Utf8\",\n      \"value\": \"x\\ude00\"\n"
+        )));
+        assert!(json.contains(&format!(
+            "\"index\": {string},\n      \"tag\": \"String\",\n      \"value\": \"x\\ude00\",\n"
+        )));
+    }
+}
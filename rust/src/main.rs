@@ -1,149 +1,144 @@
 #![allow(unused)]
 
-use nonesense::ConstantPoolItem as ConstantPoolItem;
-use nonesense::parse_access_flags as parse_access_flags;
-use nonesense::parse_constant_pool as parse_constant_pool;
-use nonesense::parse_constant_pool_tag as parse_constant_pool_tag;
-use nonesense::parse_u16 as parse_u16;
+use nonesense::ClassFile;
+use nonesense::ReadError;
+use nonesense::SupportedVersions;
 
-use std::collections::HashSet;
-use std::iter::Iterator;
+use std::path::PathBuf;
 use std::process::exit;
 use std::vec::Vec;
 
 fn main() {
     // Let's handle basic command line arguments and reading the file.
     let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        eprintln!("I need a least 2 arguments.");
+    let mut format = "text";
+    let mut versions = SupportedVersions::default();
+    let mut positional: Vec<&str> = Vec::with_capacity(args.len());
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--format" {
+            match iter.next() {
+                Some(value) => format = value,
+                None => {
+                    eprintln!("--format needs to be followed by text or json.");
+                    exit(1);
+                }
+            }
+        } else if let Some(value) = arg.strip_prefix("--format=") {
+            format = value;
+        } else if arg == "--enable-preview" {
+            // Like java, accept class files that use the preview features
+            // of the newest release.
+            versions.preview = true;
+        } else {
+            positional.push(arg);
+        }
+    }
+    if format != "text" && format != "json" {
+        eprintln!("I do not know the {format} format, use text or json.");
         exit(1);
     }
-    let working_directory = std::env::current_dir();
-    if working_directory.is_err() {
-        eprintln!("Please fix {}", working_directory.err().unwrap());
-        exit(2);
+    let disasm = positional.first() == Some(&"disasm");
+    if disasm {
+        positional.remove(0);
     }
-    let working_directory = working_directory.unwrap();
-    let class_file_path = working_directory.join(args[1].to_owned());
-    let class_file_data = std::fs::read(&class_file_path);
-    if class_file_data.is_err() {
+    if positional.is_empty() {
         eprintln!(
-            "I was unable to read {class_file_path:?} because of {}.",
-            class_file_data.err().unwrap()
+            "Usage: {} [disasm] [--format text|json] [--enable-preview] <class or - for standard input>",
+            args[0]
         );
-        exit(3);
+        exit(1);
     }
-    let class_file_data = class_file_data.unwrap();
-    // Now we can get around to parsing a class file.
-    // The first 4 bytes are going to be the magic value.
-    let magic = &class_file_data[0..4];
-    if magic != [0xCA, 0xFE, 0xBA, 0xBE] {
-        eprintln!("Invalid magic value:");
-        exit(4);
+    let (class_file_path, class_file) = read_class_file(positional[0], &versions);
+    if format == "json" {
+        print!("{}", class_file.to_json());
+        exit(0);
+    }
+    if disasm {
+        println!("Classfile {}", class_file_path.display());
+        print!("{}", class_file.disassemble());
+        exit(0);
     }
-    println!("I read the expected magic value of {:X?}", &magic);
-    let minor_version = parse_u16(&class_file_data[4..6]);
-    let major_version = parse_u16(&class_file_data[6..8]);
     println!(
-        "This class file uses version {major_version}.{minor_version} of \
-the class file format."
+        "This class file uses version {} of the class file format.",
+        class_file.version()
     );
-    let constant_pool_count = parse_u16(&class_file_data[8..10]) - 1;
-    let mut constant_pool: Vec<ConstantPoolItem> = Vec::with_capacity(constant_pool_count as usize);
-    println!("There are {constant_pool_count} items in the contant_pool.");
-    let mut raw_class_file_data = class_file_data[10..].iter();
-    for _ in 1..=constant_pool_count {
-        match parse_constant_pool_tag(&mut raw_class_file_data.by_ref().copied()) {
-            Some(t) => {
-                constant_pool.push(t.to_owned());
-            }
-            None => {
-                break;
-            }
-        }
-    }
-    println!("The constant pool has been read.");
-    if constant_pool.len() != constant_pool_count as usize {
-        eprintln!("Unable to read the constant_pool correctly.");
-        dbg!(constant_pool);
-        exit(5);
-    }
-    if parse_constant_pool(&constant_pool) {
-        println!("Parsed constant pool correctly.");
-    } else {
-        eprintln!("Unable to parse constant pool correctly.");
-        dbg!(constant_pool);
-        exit(5);
-    }
-    let mut temp: Vec<u8> = raw_class_file_data.by_ref().take(2).copied().collect();
-    if temp.len() != 2 {
-        eprintln!("Access Flags are missing from the class file.");
-        exit(6);
-    }
-    let access_flags = parse_access_flags(parse_u16(&temp[..]));
+    println!(
+        "There are {} items in the constant_pool.",
+        class_file.constant_pool.len()
+    );
+    println!("Constant pool:");
+    print!("{}", class_file.constant_pool);
+    let access_flags = class_file.flags();
     println!("The following Access Flags were set: {access_flags:?}");
-    temp = raw_class_file_data.by_ref().take(2).copied().collect();
-    if temp.len() != 2 {
-        eprintln!("The 'this_class' item is missing from the class file.");
-        exit(7);
+    if let Err(violations) = access_flags.validate(class_file.version()) {
+        for violation in violations {
+            println!("The access flags are not allowed: {violation}.");
+        }
     }
-    let this_class: u16 = parse_u16(&temp[..]);
     print!("This file defines the class described in Constant Pool Entry");
-    println!(" {this_class}.");
-    temp = raw_class_file_data.by_ref().take(2).copied().collect();
-    if temp.len() != 2 {
-        eprintln!("The 'super_class' item is missing from the class file.");
-        exit(7);
-    }
-    let super_class: u16 = parse_u16(&temp[..]);
+    println!(" {}.", class_file.this_class);
     print!("This class has the superclass described in Constant Pool Entry");
-    println!(" {super_class}.");
-    temp = raw_class_file_data.by_ref().take(2).copied().collect();
-    if temp.len() != 2 {
-        eprintln!("The 'interfaces_count' item is missing from the class file.");
-        exit(7);
-    }
-    let interfaces_count: u16 = parse_u16(&temp[..]);
-    println!("This class implements {interfaces_count} interfaces.");
-    if interfaces_count != 0 {
-        eprintln!("I cannot parse the interfaces currently.");
-        exit(0);
-    }
-    temp = raw_class_file_data.by_ref().take(2).copied().collect();
-    if temp.len() != 2 {
-        eprintln!("The 'fields_count' item is missing from the class file.");
-        exit(7);
-    }
-    let fields_count: u16 = parse_u16(&temp[..]);
-    println!("This class has {fields_count} fields.");
-    if fields_count != 0 {
-        eprintln!("I cannot parse the fields currently.");
-        exit(0);
+    println!(" {}.", class_file.super_class);
+    println!(
+        "This class implements {} interfaces.",
+        class_file.interfaces.len()
+    );
+    for interface in &class_file.interfaces {
+        println!("It implements {}.", interface.name);
     }
-    temp = raw_class_file_data.by_ref().take(2).copied().collect();
-    if temp.len() != 2 {
-        eprintln!("The 'methods_count' item is missing from the class file.");
-        exit(7);
+    println!("This class has {} fields.", class_file.fields.len());
+    println!(
+        "This class implements {} methods.",
+        class_file.methods.len()
+    );
+    println!(
+        "There are {} attributes in this class.",
+        class_file.attributes.len()
+    );
+    exit(0);
+}
+
+/// Reads and parses the class file at `path`, relative to the working
+/// directory, or from standard input when `path` is `-`, as long as its
+/// version is one of `versions`. Exits with an error message when that
+/// fails.
+fn read_class_file(path: &str, versions: &SupportedVersions) -> (PathBuf, ClassFile) {
+    if path == "-" {
+        return match ClassFile::read_from_with_versions(std::io::stdin().lock(), versions) {
+            Ok(class_file) => (PathBuf::from(path), class_file),
+            Err(error) => {
+                eprintln!("Standard input is not a valid class file: {error}.");
+                exit(4);
+            }
+        };
     }
-    let methods_count: u16 = parse_u16(&temp[..]);
-    println!("This class implements {methods_count} methods.");
-    if methods_count != 0 {
-        todo!("Parse the methods.");
+    let working_directory = std::env::current_dir();
+    if working_directory.is_err() {
+        eprintln!("Please fix {}", working_directory.err().unwrap());
+        exit(2);
     }
-    temp = raw_class_file_data.by_ref().take(2).copied().collect();
-    if temp.len() != 2 {
+    let working_directory = working_directory.unwrap();
+    let class_file_path = working_directory.join(path);
+    let class_file = std::fs::File::open(&class_file_path);
+    if class_file.is_err() {
         eprintln!(
-            "The 'attributes_count' item is missing from the class \
-file."
+            "I was unable to read {class_file_path:?} because of {}.",
+            class_file.err().unwrap()
         );
-        exit(7);
+        exit(3);
+    }
+    // Now we can get around to parsing a class file.
+    match ClassFile::read_from_with_versions(std::io::BufReader::new(class_file.unwrap()), versions)
+    {
+        Ok(class_file) => (class_file_path, class_file),
+        Err(ReadError::Io(error)) => {
+            eprintln!("I was unable to read {class_file_path:?} because of {error}.");
+            exit(3);
+        }
+        Err(error) => {
+            eprintln!("{class_file_path:?} is not a valid class file: {error}.");
+            exit(4);
+        }
     }
-    let attributes_count: u16 = parse_u16(&temp[..]);
-    println!("There are {attributes_count} attributes in this class.");
-    println!(
-        "There are {} bytes of unprocessed input left.",
-        raw_class_file_data.count()
-    );
-    exit(0);
 }
-
@@ -0,0 +1,305 @@
+use crate::classfile::{MAGIC, Parser};
+use crate::constantpool::RawConstant;
+use crate::error::{ClassFormatError, ConstantPoolError, Structure};
+use crate::{
+    ClassFileVersion, JavaString, SupportedVersions, encode_javaized_utf8, parse_javaized_utf8,
+};
+
+/// A class file read in place, for scanning many classes when only a few of
+/// their constants matter.
+///
+/// The constant pool entries borrow their bytes from the input and are only
+/// decoded when they are asked for. Reading checks the magic, the layout of
+/// the constant pool and the items up to the interfaces, and leaves the
+/// fields, methods and attributes alone. [`crate::ClassFile::parse`] checks
+/// the whole class file.
+#[derive(Clone, Debug)]
+pub struct ClassReader<'a> {
+    pub minor_version: u16,
+    pub major_version: u16,
+    /// Entry `n` of the constant pool is stored at position `n - 1`.
+    constants: Vec<Option<RawConstant<'a>>>,
+    pub access_flags: u16,
+    /// The constant pool index of the class defined by this file.
+    pub this_class: u16,
+    /// The constant pool index of the direct superclass, or 0 for
+    /// `java/lang/Object`.
+    pub super_class: u16,
+    /// The constant pool indices of the direct superinterfaces.
+    pub interfaces: Vec<u16>,
+}
+
+/// The undecoded contents of a `CONSTANT_Utf8` entry, borrowed from the
+/// class file.
+#[derive(Clone, Copy, Debug)]
+pub struct Utf8Ref<'a> {
+    bytes: &'a [u8],
+    /// The offset of `bytes` in the class file.
+    offset: usize,
+    index: u16,
+}
+
+impl<'a> ClassReader<'a> {
+    /// Reads the start of a class file, up to and including the interfaces.
+    pub fn new(input: &'a [u8]) -> Result<ClassReader<'a>, ClassFormatError> {
+        ClassReader::read_supported(input, None)
+    }
+
+    /// Reads the start of a class file like [`ClassReader::new`], rejecting
+    /// it when its version is not one of `versions`.
+    pub fn with_versions(
+        input: &'a [u8],
+        versions: &SupportedVersions,
+    ) -> Result<ClassReader<'a>, ClassFormatError> {
+        ClassReader::read_supported(input, Some(versions))
+    }
+
+    fn read_supported(
+        input: &'a [u8],
+        versions: Option<&SupportedVersions>,
+    ) -> Result<ClassReader<'a>, ClassFormatError> {
+        let mut parser = Parser::new(input);
+        if parser.split(MAGIC.len()).map(|magic| magic.remaining()) != Some(&MAGIC[..]) {
+            return Err(ClassFormatError::BadMagic);
+        }
+        let minor_version = parser.u16(Structure::Version)?;
+        let major_version = parser.u16(Structure::Version)?;
+        if let Some(versions) = versions {
+            versions.check(ClassFileVersion::new(major_version, minor_version))?;
+        }
+        let constant_pool_count = parser.u16(Structure::ConstantPool)?;
+        let constants = parser.raw_constant_pool(constant_pool_count, major_version)?;
+        let access_flags = parser.u16(Structure::AccessFlags)?;
+        let this_class = parser.u16(Structure::ThisClass)?;
+        let super_class = parser.u16(Structure::SuperClass)?;
+        let interfaces = parser.table(Structure::Interfaces, |parser| {
+            parser.u16(Structure::Interfaces)
+        })?;
+        Ok(ClassReader {
+            minor_version,
+            major_version,
+            constants,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+        })
+    }
+
+    /// The version of the class file format it uses.
+    pub fn version(&self) -> ClassFileVersion {
+        ClassFileVersion::new(self.major_version, self.minor_version)
+    }
+
+    /// The number of entries in the constant pool, counting the unusable
+    /// ones after `Long` and `Double` entries.
+    pub fn constant_pool_len(&self) -> usize {
+        self.constants.len()
+    }
+
+    fn raw(&self, index: u16) -> Result<&RawConstant<'a>, ConstantPoolError> {
+        index
+            .checked_sub(1)
+            .and_then(|position| self.constants.get(position as usize)?.as_ref())
+            .ok_or(ConstantPoolError::InvalidIndex(index))
+    }
+
+    /// The tag of the constant pool entry at `index`.
+    pub fn tag(&self, index: u16) -> Result<u8, ConstantPoolError> {
+        self.raw(index).map(|constant| constant.tag)
+    }
+
+    /// The `CONSTANT_Utf8` entry at `index`.
+    pub fn utf8(&self, index: u16) -> Result<Utf8Ref<'a>, ConstantPoolError> {
+        let constant = self.raw(index)?;
+        if constant.tag != 1 {
+            return Err(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "Utf8",
+            });
+        }
+        Ok(Utf8Ref {
+            bytes: constant.info.remaining(),
+            offset: constant.info.offset(),
+            index,
+        })
+    }
+
+    /// The internal name of the `CONSTANT_Class` entry at `index`.
+    pub fn class_name(&self, index: u16) -> Result<Utf8Ref<'a>, ConstantPoolError> {
+        let constant = self.raw(index)?;
+        if constant.tag != 7 {
+            return Err(ConstantPoolError::UnexpectedType {
+                index,
+                expected: "Class",
+            });
+        }
+        let [high, low] = constant.info.remaining() else {
+            unreachable!("a Class entry has two bytes of info")
+        };
+        self.utf8(u16::from_be_bytes([*high, *low]))
+    }
+
+    /// The internal names of every `CONSTANT_Class` entry, which covers all
+    /// of the classes the class file refers to apart from those that only
+    /// appear in descriptors.
+    pub fn class_names(&self) -> impl Iterator<Item = Utf8Ref<'a>> + '_ {
+        (1..=self.constants.len() as u16).filter_map(|index| self.class_name(index).ok())
+    }
+}
+
+impl<'a> Utf8Ref<'a> {
+    /// The modified UTF-8 bytes of the entry.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decodes the entry.
+    pub fn decode(&self) -> Result<JavaString, ClassFormatError> {
+        parse_javaized_utf8(self.bytes).map_err(|position| ClassFormatError::MalformedUtf8 {
+            offset: self.offset + position,
+            index: self.index,
+        })
+    }
+}
+
+/// Compares the entry with a string without decoding it, since every string
+/// has exactly one modified UTF-8 form.
+impl PartialEq<str> for Utf8Ref<'_> {
+    fn eq(&self, other: &str) -> bool {
+        if other.bytes().all(|byte| (0x01..0x80).contains(&byte)) {
+            self.bytes == other.as_bytes()
+        } else {
+            self.bytes == encode_javaized_utf8(other)
+        }
+    }
+}
+
+impl PartialEq<&str> for Utf8Ref<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ClassBuilder;
+    use crate::{ClassFile, ConstantPoolItem};
+
+    const PACKAGE_INFO: &[u8] = include_bytes!("../../package-info.class");
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/Sample.class");
+
+    /// The kind of entry each tag stands for.
+    const TAGS: [(u8, &str); 17] = [
+        (1, "Utf8"),
+        (3, "Integer"),
+        (4, "Float"),
+        (5, "Long"),
+        (6, "Double"),
+        (7, "Class"),
+        (8, "String"),
+        (9, "Fieldref"),
+        (10, "Methodref"),
+        (11, "InterfaceMethodref"),
+        (12, "NameAndType"),
+        (15, "MethodHandle"),
+        (16, "MethodType"),
+        (17, "Dynamic"),
+        (18, "InvokeDynamic"),
+        (19, "Module"),
+        (20, "Package"),
+    ];
+
+    #[test]
+    fn agrees_with_the_class_file() {
+        for input in [PACKAGE_INFO, SAMPLE] {
+            let reader = ClassReader::new(input).unwrap();
+            let class_file = ClassFile::parse(input).unwrap();
+            assert_eq!(reader.version(), class_file.version());
+            assert_eq!(reader.access_flags, class_file.access_flags);
+            assert_eq!(reader.this_class, class_file.this_class);
+            assert_eq!(reader.super_class, class_file.super_class);
+            let interfaces: Vec<u16> = class_file
+                .interfaces
+                .iter()
+                .map(|interface| interface.index)
+                .collect();
+            assert_eq!(reader.interfaces, interfaces);
+
+            let constant_pool = &class_file.constant_pool;
+            assert_eq!(reader.constant_pool_len(), constant_pool.len());
+            for index in 1..=constant_pool.len() as u16 {
+                let Ok(item) = constant_pool.get(index) else {
+                    // The slot after a Long or Double.
+                    assert_eq!(
+                        reader.tag(index),
+                        Err(ConstantPoolError::InvalidIndex(index))
+                    );
+                    continue;
+                };
+                let tag = reader.tag(index).unwrap();
+                assert!(TAGS.contains(&(tag, item.kind())), "#{index}");
+                if let ConstantPoolItem::Utf8(data) = item {
+                    assert_eq!(reader.utf8(index).unwrap().decode().as_ref(), Ok(data));
+                }
+            }
+            let names: Vec<JavaString> = reader
+                .class_names()
+                .map(|name| name.decode().unwrap())
+                .collect();
+            let expected: Vec<JavaString> = (1..=constant_pool.len() as u16)
+                .filter_map(|index| constant_pool.class_name(index).ok())
+                .map(JavaString::from)
+                .collect();
+            assert_eq!(names, expected);
+        }
+    }
+
+    #[test]
+    fn checks_the_version_when_asked_to() {
+        let java_8 = SupportedVersions {
+            oldest_major: 45,
+            newest_major: 52,
+            preview: false,
+        };
+        assert!(ClassReader::with_versions(PACKAGE_INFO, &java_8).is_ok());
+        assert_eq!(
+            ClassReader::with_versions(SAMPLE, &java_8).err(),
+            Some(ClassFormatError::UnsupportedVersion {
+                major: 61,
+                minor: 0
+            })
+        );
+        assert!(ClassReader::new(SAMPLE).is_ok());
+    }
+
+    #[test]
+    fn compares_entries_with_strings() {
+        let mut class = ClassBuilder::new(52);
+        let strings = [
+            "java/lang/Object",
+            "",
+            "h\u{E9}llo\0",
+            "\u{20AC}",
+            "\u{1F600} world",
+            "\u{10FFFF}",
+        ];
+        let indices = strings.map(|string| class.utf8(string));
+        let bytes = class.build();
+        let reader = ClassReader::new(&bytes).unwrap();
+        for (index, string) in indices.into_iter().zip(strings) {
+            let entry = reader.utf8(index).unwrap();
+            assert!(entry == string, "{string:?}");
+            assert!(entry == *string, "{string:?}");
+            for other in strings.iter().filter(|&&other| other != string) {
+                assert!(entry != *other, "{string:?} == {other:?}");
+            }
+        }
+        // The supplementary character is compared as a surrogate pair, not
+        // as the four byte UTF-8 form.
+        let emoji = reader.utf8(indices[4]).unwrap();
+        assert_eq!(emoji.as_bytes().len(), 12);
+        assert!(emoji != "\u{1F600} worl");
+    }
+}
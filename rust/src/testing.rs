@@ -0,0 +1,271 @@
+//! Class files built by hand for the unit tests.
+
+use crate::{ClassFile, ConstantPool, encode_javaized_utf8};
+use std::collections::HashMap;
+
+/// A class file under construction. It starts out with the entries for the
+/// class `Test` and its superclass `java/lang/Object` at indices 2 and 4 of
+/// the constant pool.
+pub(crate) struct ClassBuilder {
+    pub(crate) minor_version: u16,
+    pub(crate) major_version: u16,
+    pub(crate) access_flags: u16,
+    pub(crate) this_class: u16,
+    pub(crate) super_class: u16,
+    pub(crate) interfaces: Vec<u16>,
+    constants: Vec<u8>,
+    /// The offset in `constants` of each entry, by index.
+    offsets: Vec<(u16, usize)>,
+    /// The index the next entry gets.
+    next_index: u16,
+    /// The `Utf8` and `Class` entries that were added, which are shared like
+    /// javac does, keyed by their tag and string.
+    shared: HashMap<(u8, String), u16>,
+    fields: Vec<Vec<u8>>,
+    methods: Vec<Vec<u8>>,
+    attributes: Vec<Vec<u8>>,
+}
+
+impl ClassBuilder {
+    pub(crate) fn new(major_version: u16) -> ClassBuilder {
+        let mut builder = ClassBuilder {
+            minor_version: 0,
+            major_version,
+            access_flags: 0x0021,
+            this_class: 0,
+            super_class: 0,
+            interfaces: Vec::new(),
+            constants: Vec::new(),
+            offsets: Vec::new(),
+            next_index: 1,
+            shared: HashMap::new(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+            attributes: Vec::new(),
+        };
+        builder.this_class = builder.class("Test");
+        builder.super_class = builder.class("java/lang/Object");
+        builder
+    }
+
+    /// Adds an entry from its tag and info bytes, taking up two indices for
+    /// a `Long` or `Double`.
+    pub(crate) fn constant(&mut self, tag: u8, info: &[u8]) -> u16 {
+        let index = self.next_index;
+        self.offsets.push((index, self.constants.len()));
+        self.constants.push(tag);
+        self.constants.extend_from_slice(info);
+        self.next_index += if matches!(tag, 5 | 6) { 2 } else { 1 };
+        index
+    }
+
+    /// Adds a `Utf8` entry holding `bytes` as they are.
+    pub(crate) fn utf8_bytes(&mut self, bytes: &[u8]) -> u16 {
+        let mut info = (bytes.len() as u16).to_be_bytes().to_vec();
+        info.extend_from_slice(bytes);
+        self.constant(1, &info)
+    }
+
+    pub(crate) fn utf8(&mut self, value: &str) -> u16 {
+        if let Some(&index) = self.shared.get(&(1, value.to_string())) {
+            return index;
+        }
+        let index = self.utf8_bytes(&encode_javaized_utf8(value));
+        self.shared.insert((1, value.to_string()), index);
+        index
+    }
+
+    pub(crate) fn integer(&mut self, value: i32) -> u16 {
+        self.constant(3, &value.to_be_bytes())
+    }
+
+    pub(crate) fn long(&mut self, value: i64) -> u16 {
+        self.constant(5, &value.to_be_bytes())
+    }
+
+    pub(crate) fn double(&mut self, value: f64) -> u16 {
+        self.constant(6, &value.to_be_bytes())
+    }
+
+    /// Adds an entry that refers to other entries by their indices.
+    pub(crate) fn reference(&mut self, tag: u8, indices: &[u16]) -> u16 {
+        let info: Vec<u8> = indices
+            .iter()
+            .flat_map(|index| index.to_be_bytes())
+            .collect();
+        self.constant(tag, &info)
+    }
+
+    pub(crate) fn class(&mut self, name: &str) -> u16 {
+        if let Some(&index) = self.shared.get(&(7, name.to_string())) {
+            return index;
+        }
+        let name_index = self.utf8(name);
+        let index = self.reference(7, &[name_index]);
+        self.shared.insert((7, name.to_string()), index);
+        index
+    }
+
+    pub(crate) fn string(&mut self, value: &str) -> u16 {
+        let value = self.utf8(value);
+        self.reference(8, &[value])
+    }
+
+    pub(crate) fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        self.reference(12, &[name, descriptor])
+    }
+
+    /// Adds a `Fieldref` (tag 9), `Methodref` (10) or `InterfaceMethodref`
+    /// (11) entry.
+    pub(crate) fn member_ref(&mut self, tag: u8, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.reference(tag, &[class, name_and_type])
+    }
+
+    /// The offset of the entry at `index` in the built class file.
+    pub(crate) fn offset_of(&self, index: u16) -> usize {
+        let (_, offset) = self
+            .offsets
+            .iter()
+            .find(|(entry, _)| *entry == index)
+            .expect("no entry at the index");
+        10 + offset
+    }
+
+    /// The offset the access_flags item will have in the built class file.
+    pub(crate) fn end_of_constant_pool(&self) -> usize {
+        10 + self.constants.len()
+    }
+
+    /// Builds an attribute_info structure, adding its name to the constant
+    /// pool.
+    pub(crate) fn attribute(&mut self, name: &str, info: &[u8]) -> Vec<u8> {
+        let mut attribute = self.utf8(name).to_be_bytes().to_vec();
+        attribute.extend_from_slice(&(info.len() as u32).to_be_bytes());
+        attribute.extend_from_slice(info);
+        attribute
+    }
+
+    /// Builds a Code attribute with the given exception table, given as
+    /// `[start_pc, end_pc, handler_pc, catch_type]`, and no attributes.
+    pub(crate) fn code(
+        &mut self,
+        max_stack: u16,
+        max_locals: u16,
+        code: &[u8],
+        exception_table: &[[u16; 4]],
+    ) -> Vec<u8> {
+        let mut info = max_stack.to_be_bytes().to_vec();
+        info.extend_from_slice(&max_locals.to_be_bytes());
+        info.extend_from_slice(&(code.len() as u32).to_be_bytes());
+        info.extend_from_slice(code);
+        info.extend_from_slice(&(exception_table.len() as u16).to_be_bytes());
+        for entry in exception_table {
+            for item in entry {
+                info.extend_from_slice(&item.to_be_bytes());
+            }
+        }
+        info.extend_from_slice(&0u16.to_be_bytes());
+        self.attribute("Code", &info)
+    }
+
+    pub(crate) fn field(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) {
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        self.field_at(access_flags, name, descriptor, attributes);
+    }
+
+    /// Adds a field whose name and descriptor are the entries at the given
+    /// indices.
+    pub(crate) fn field_at(
+        &mut self,
+        access_flags: u16,
+        name_index: u16,
+        descriptor_index: u16,
+        attributes: &[Vec<u8>],
+    ) {
+        self.fields.push(member(
+            access_flags,
+            name_index,
+            descriptor_index,
+            attributes,
+        ));
+    }
+
+    pub(crate) fn method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) {
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        self.methods
+            .push(member(access_flags, name, descriptor, attributes));
+    }
+
+    /// Adds an attribute of the class itself.
+    pub(crate) fn class_attribute(&mut self, attribute: Vec<u8>) {
+        self.attributes.push(attribute);
+    }
+
+    pub(crate) fn build(&self) -> Vec<u8> {
+        let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE];
+        bytes.extend_from_slice(&self.minor_version.to_be_bytes());
+        bytes.extend_from_slice(&self.major_version.to_be_bytes());
+        bytes.extend_from_slice(&self.next_index.to_be_bytes());
+        bytes.extend_from_slice(&self.constants);
+        bytes.extend_from_slice(&self.access_flags.to_be_bytes());
+        bytes.extend_from_slice(&self.this_class.to_be_bytes());
+        bytes.extend_from_slice(&self.super_class.to_be_bytes());
+        bytes.extend_from_slice(&(self.interfaces.len() as u16).to_be_bytes());
+        for interface in &self.interfaces {
+            bytes.extend_from_slice(&interface.to_be_bytes());
+        }
+        bytes.extend_from_slice(&table(&self.fields));
+        bytes.extend_from_slice(&table(&self.methods));
+        bytes.extend_from_slice(&table(&self.attributes));
+        bytes
+    }
+
+    pub(crate) fn parse(&self) -> ClassFile {
+        ClassFile::parse(&self.build()).expect("the built class file is valid")
+    }
+
+    pub(crate) fn constant_pool(&self) -> ConstantPool {
+        self.parse().constant_pool
+    }
+}
+
+/// The bytes of a field_info or method_info structure.
+fn member(
+    access_flags: u16,
+    name_index: u16,
+    descriptor_index: u16,
+    attributes: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut member = access_flags.to_be_bytes().to_vec();
+    member.extend_from_slice(&name_index.to_be_bytes());
+    member.extend_from_slice(&descriptor_index.to_be_bytes());
+    member.extend_from_slice(&table(attributes));
+    member
+}
+
+/// A u16 count prefixed table of already encoded entries.
+fn table(entries: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = (entries.len() as u16).to_be_bytes().to_vec();
+    for entry in entries {
+        bytes.extend_from_slice(entry);
+    }
+    bytes
+}
@@ -0,0 +1,271 @@
+use crate::error::ClassFormatError;
+use std::fmt;
+
+/// The version of a class file, from its major_version and minor_version
+/// items. Versions compare by major version first.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ClassFileVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+/// The first class file version each attribute of JVMS table 4.7-B is
+/// recognised in. In older class files these are ordinary unknown
+/// attributes.
+const ATTRIBUTES: &[(&str, ClassFileVersion)] = &[
+    ("ConstantValue", ClassFileVersion::new(45, 3)),
+    ("Code", ClassFileVersion::new(45, 3)),
+    ("Exceptions", ClassFileVersion::new(45, 3)),
+    ("SourceFile", ClassFileVersion::new(45, 3)),
+    ("LineNumberTable", ClassFileVersion::new(45, 3)),
+    ("LocalVariableTable", ClassFileVersion::new(45, 3)),
+    ("InnerClasses", ClassFileVersion::new(45, 3)),
+    ("Synthetic", ClassFileVersion::new(45, 3)),
+    ("Deprecated", ClassFileVersion::new(45, 3)),
+    ("EnclosingMethod", ClassFileVersion::new(49, 0)),
+    ("Signature", ClassFileVersion::new(49, 0)),
+    ("SourceDebugExtension", ClassFileVersion::new(49, 0)),
+    ("LocalVariableTypeTable", ClassFileVersion::new(49, 0)),
+    ("RuntimeVisibleAnnotations", ClassFileVersion::new(49, 0)),
+    ("RuntimeInvisibleAnnotations", ClassFileVersion::new(49, 0)),
+    (
+        "RuntimeVisibleParameterAnnotations",
+        ClassFileVersion::new(49, 0),
+    ),
+    (
+        "RuntimeInvisibleParameterAnnotations",
+        ClassFileVersion::new(49, 0),
+    ),
+    ("AnnotationDefault", ClassFileVersion::new(49, 0)),
+    ("StackMapTable", ClassFileVersion::new(50, 0)),
+    ("BootstrapMethods", ClassFileVersion::new(51, 0)),
+    (
+        "RuntimeVisibleTypeAnnotations",
+        ClassFileVersion::new(52, 0),
+    ),
+    (
+        "RuntimeInvisibleTypeAnnotations",
+        ClassFileVersion::new(52, 0),
+    ),
+    ("MethodParameters", ClassFileVersion::new(52, 0)),
+    ("Module", ClassFileVersion::new(53, 0)),
+    ("ModulePackages", ClassFileVersion::new(53, 0)),
+    ("ModuleMainClass", ClassFileVersion::new(53, 0)),
+    ("NestHost", ClassFileVersion::new(55, 0)),
+    ("NestMembers", ClassFileVersion::new(55, 0)),
+    ("Record", ClassFileVersion::new(60, 0)),
+    ("PermittedSubclasses", ClassFileVersion::new(61, 0)),
+];
+
+impl ClassFileVersion {
+    /// The major version of the oldest class files, those of Java 1.0.2
+    /// and 1.1.
+    pub const OLDEST_MAJOR: u16 = 45;
+    /// The major version of the newest Java release this crate knows, Java
+    /// 27.
+    pub const LATEST_MAJOR: u16 = 71;
+    /// The minor version of class files that use the preview features of
+    /// their Java release.
+    pub const PREVIEW_MINOR: u16 = 0xFFFF;
+    /// The first major version that can depend on preview features.
+    const FIRST_PREVIEW_MAJOR: u16 = 56;
+
+    pub const fn new(major: u16, minor: u16) -> ClassFileVersion {
+        ClassFileVersion { major, minor }
+    }
+
+    /// The newest class file version this crate knows.
+    pub const fn latest() -> ClassFileVersion {
+        ClassFileVersion::new(ClassFileVersion::LATEST_MAJOR, 0)
+    }
+
+    /// The feature release of Java the major version belongs to, where 1
+    /// stands for Java 1.0.2 and 1.1 and 2 to 4 for Java 1.2 to 1.4. Major
+    /// versions outside of 45 to [`ClassFileVersion::LATEST_MAJOR`] have
+    /// none.
+    pub fn java_release(&self) -> Option<u16> {
+        (ClassFileVersion::OLDEST_MAJOR..=ClassFileVersion::LATEST_MAJOR)
+            .contains(&self.major)
+            .then(|| self.major - 44)
+    }
+
+    /// Whether the class file depends on the preview features of its Java
+    /// release.
+    pub fn is_preview(&self) -> bool {
+        self.major >= ClassFileVersion::FIRST_PREVIEW_MAJOR
+            && self.minor == ClassFileVersion::PREVIEW_MINOR
+    }
+
+    /// Whether the constant pool may hold entries with `tag`.
+    pub fn supports_constant_tag(&self, tag: u8) -> bool {
+        self.major >= first_major_version(tag)
+    }
+
+    /// Whether the attribute called `name` is recognised, which is the case
+    /// for the attributes the specification defines by the version they
+    /// were introduced in. Other attributes are never recognised.
+    pub fn supports_attribute(&self, name: &str) -> bool {
+        ATTRIBUTES
+            .iter()
+            .any(|(attribute, since)| *attribute == name && self >= since)
+    }
+
+    /// The access flags a class may have.
+    pub fn class_access_flags(&self) -> u16 {
+        let mut flags = 0x0001 | 0x0010 | 0x0020 | 0x0200 | 0x0400;
+        if self.major >= 49 {
+            flags |= 0x1000 | 0x2000 | 0x4000;
+        }
+        if self.major >= 53 {
+            flags |= 0x8000;
+        }
+        flags
+    }
+
+    /// The access flags a field may have.
+    pub fn field_access_flags(&self) -> u16 {
+        let mut flags = 0x0001 | 0x0002 | 0x0004 | 0x0008 | 0x0010 | 0x0040 | 0x0080;
+        if self.major >= 49 {
+            flags |= 0x1000 | 0x4000;
+        }
+        flags
+    }
+
+    /// The access flags a method may have. `ACC_STRICT` only exists from
+    /// Java 1.2 to Java 16, after which all floating point is strict.
+    pub fn method_access_flags(&self) -> u16 {
+        let mut flags = 0x0001 | 0x0002 | 0x0004 | 0x0008 | 0x0010 | 0x0020 | 0x0100 | 0x0400;
+        if (46..=60).contains(&self.major) {
+            flags |= 0x0800;
+        }
+        if self.major >= 49 {
+            flags |= 0x0040 | 0x0080 | 0x1000;
+        }
+        flags
+    }
+}
+
+/// The first class file major version that allows each constant pool tag.
+fn first_major_version(tag: u8) -> u16 {
+    match tag {
+        15 | 16 | 18 => 51,
+        19 | 20 => 53,
+        17 => 55,
+        _ => 45,
+    }
+}
+
+/// Shows the version as `major.minor` along with its Java release, such as
+/// `61.0 (Java 17)`.
+impl fmt::Display for ClassFileVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        match self.java_release() {
+            Some(1) => write!(f, " (Java 1.1)"),
+            Some(release @ 2..=4) => write!(f, " (Java 1.{release})"),
+            Some(release) if self.is_preview() => write!(f, " (Java {release} preview)"),
+            Some(release) => write!(f, " (Java {release})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The range of class file versions a virtual machine can run.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SupportedVersions {
+    pub oldest_major: u16,
+    pub newest_major: u16,
+    /// Whether class files that depend on preview features are accepted.
+    /// Like in the JDK, only those of the `newest_major` release are.
+    pub preview: bool,
+}
+
+impl SupportedVersions {
+    /// Checks that a class file of `version` can be run.
+    pub fn check(&self, version: ClassFileVersion) -> Result<(), ClassFormatError> {
+        let supported = (self.oldest_major..=self.newest_major).contains(&version.major)
+            && if version.major < ClassFileVersion::FIRST_PREVIEW_MAJOR {
+                true
+            } else if version.minor == ClassFileVersion::PREVIEW_MINOR {
+                self.preview && version.major == self.newest_major
+            } else {
+                // Since Java 12 the minor version only marks preview
+                // features.
+                version.minor == 0
+            };
+        if supported {
+            Ok(())
+        } else {
+            Err(ClassFormatError::UnsupportedVersion {
+                major: version.major,
+                minor: version.minor,
+            })
+        }
+    }
+}
+
+/// Every version from 45 to [`ClassFileVersion::LATEST_MAJOR`], without
+/// preview features.
+impl Default for SupportedVersions {
+    fn default() -> Self {
+        SupportedVersions {
+            oldest_major: ClassFileVersion::OLDEST_MAJOR,
+            newest_major: ClassFileVersion::LATEST_MAJOR,
+            preview: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_java_releases() {
+        assert_eq!(ClassFileVersion::new(45, 3).to_string(), "45.3 (Java 1.1)");
+        assert_eq!(ClassFileVersion::new(48, 0).to_string(), "48.0 (Java 1.4)");
+        assert_eq!(ClassFileVersion::new(61, 0).to_string(), "61.0 (Java 17)");
+        assert_eq!(
+            ClassFileVersion::new(65, 0xFFFF).to_string(),
+            "65.65535 (Java 21 preview)"
+        );
+        assert_eq!(ClassFileVersion::new(44, 0).to_string(), "44.0");
+    }
+
+    #[test]
+    fn checks_the_supported_range() {
+        let versions = SupportedVersions {
+            oldest_major: 45,
+            newest_major: 65,
+            preview: true,
+        };
+        assert!(versions.check(ClassFileVersion::new(45, 3)).is_ok());
+        assert!(versions.check(ClassFileVersion::new(52, 0)).is_ok());
+        assert!(versions.check(ClassFileVersion::new(65, 0xFFFF)).is_ok());
+        assert!(versions.check(ClassFileVersion::new(44, 0)).is_err());
+        assert!(versions.check(ClassFileVersion::new(66, 0)).is_err());
+        assert!(versions.check(ClassFileVersion::new(61, 0xFFFF)).is_err());
+        assert!(versions.check(ClassFileVersion::new(61, 1)).is_err());
+        let versions = SupportedVersions {
+            preview: false,
+            ..versions
+        };
+        assert!(versions.check(ClassFileVersion::new(65, 0xFFFF)).is_err());
+    }
+
+    #[test]
+    fn gates_features_by_version() {
+        let java_6 = ClassFileVersion::new(50, 0);
+        assert!(!java_6.supports_constant_tag(18));
+        assert!(ClassFileVersion::new(51, 0).supports_constant_tag(18));
+        assert!(java_6.supports_attribute("StackMapTable"));
+        assert!(!java_6.supports_attribute("BootstrapMethods"));
+        assert!(!ClassFileVersion::new(45, 0).supports_attribute("Code"));
+        assert_eq!(java_6.class_access_flags() & 0x8000, 0);
+        assert_ne!(java_6.method_access_flags() & 0x0800, 0);
+        assert_eq!(
+            ClassFileVersion::new(61, 0).method_access_flags() & 0x0800,
+            0
+        );
+    }
+}
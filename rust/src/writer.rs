@@ -0,0 +1,300 @@
+use crate::classfile::MAGIC;
+use crate::{
+    Attribute, AttributeInfo, ClassFile, ConstantPool, ConstantPoolItem, encode_javaized_utf16,
+};
+
+/// Builds the bytes of a class file, the counterpart of the parser.
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Writes the u16 count of a table, which has to fit in a class file.
+    fn count(&mut self, count: usize, table: &str) {
+        let count = u16::try_from(count)
+            .unwrap_or_else(|_| panic!("too many {table} for a class file: {count}"));
+        self.u16(count);
+    }
+
+    /// Writes a u16 count prefixed table, writing each entry with `entry`.
+    fn table<T>(&mut self, table: &str, entries: &[T], mut entry: impl FnMut(&mut Self, &T)) {
+        self.count(entries.len(), table);
+        for item in entries {
+            entry(self, item);
+        }
+    }
+
+    fn constant_pool(&mut self, constant_pool: &ConstantPool) {
+        self.count(constant_pool.len() + 1, "constant pool entries");
+        for (_, item) in constant_pool.iter() {
+            match item {
+                ConstantPoolItem::Utf8(data) => {
+                    let encoded = encode_javaized_utf16(data.code_units());
+                    self.u8(1);
+                    self.count(encoded.len(), "bytes in a Utf8 constant");
+                    self.bytes.extend_from_slice(&encoded);
+                }
+                ConstantPoolItem::Integer(value) => {
+                    self.u8(3);
+                    self.bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolItem::Float(value) => {
+                    self.u8(4);
+                    self.bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolItem::Long(value) => {
+                    self.u8(5);
+                    self.bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolItem::Double(value) => {
+                    self.u8(6);
+                    self.bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolItem::Class(index) => {
+                    self.u8(7);
+                    self.u16(*index);
+                }
+                ConstantPoolItem::String(index) => {
+                    self.u8(8);
+                    self.u16(*index);
+                }
+                ConstantPoolItem::Fieldref(class_index, name_and_type_index) => {
+                    self.u8(9);
+                    self.u16(*class_index);
+                    self.u16(*name_and_type_index);
+                }
+                ConstantPoolItem::Methodref(class_index, name_and_type_index) => {
+                    self.u8(10);
+                    self.u16(*class_index);
+                    self.u16(*name_and_type_index);
+                }
+                ConstantPoolItem::InterfaceMethodref(class_index, name_and_type_index) => {
+                    self.u8(11);
+                    self.u16(*class_index);
+                    self.u16(*name_and_type_index);
+                }
+                ConstantPoolItem::NameAndType(name_index, descriptor_index) => {
+                    self.u8(12);
+                    self.u16(*name_index);
+                    self.u16(*descriptor_index);
+                }
+                ConstantPoolItem::MethodHandle(kind, reference_index) => {
+                    self.u8(15);
+                    self.u8(*kind);
+                    self.u16(*reference_index);
+                }
+                ConstantPoolItem::MethodType(descriptor_index) => {
+                    self.u8(16);
+                    self.u16(*descriptor_index);
+                }
+                ConstantPoolItem::Dynamic(bootstrap, name_and_type_index) => {
+                    self.u8(17);
+                    self.u16(*bootstrap);
+                    self.u16(*name_and_type_index);
+                }
+                ConstantPoolItem::InvokeDynamic(bootstrap, name_and_type_index) => {
+                    self.u8(18);
+                    self.u16(*bootstrap);
+                    self.u16(*name_and_type_index);
+                }
+                ConstantPoolItem::Module(name_index) => {
+                    self.u8(19);
+                    self.u16(*name_index);
+                }
+                ConstantPoolItem::Package(name_index) => {
+                    self.u8(20);
+                    self.u16(*name_index);
+                }
+            }
+        }
+    }
+
+    fn attributes(&mut self, attributes: &[AttributeInfo]) {
+        self.table("attributes", attributes, |writer, info| {
+            writer.u16(info.name_index);
+            let mut body = Writer { bytes: Vec::new() };
+            body.attribute(&info.attribute);
+            let length = u32::try_from(body.bytes.len())
+                .unwrap_or_else(|_| panic!("the {} attribute is too long", info.attribute.name()));
+            writer.u32(length);
+            writer.bytes.extend_from_slice(&body.bytes);
+        });
+    }
+
+    /// Writes the info item of an attribute.
+    fn attribute(&mut self, attribute: &Attribute) {
+        match attribute {
+            Attribute::ConstantValue(index)
+            | Attribute::Signature(index)
+            | Attribute::SourceFile(index) => self.u16(*index),
+            Attribute::Code(code) => {
+                self.u16(code.max_stack);
+                self.u16(code.max_locals);
+                let length = u32::try_from(code.code.len())
+                    .unwrap_or_else(|_| panic!("too much code for a class file"));
+                self.u32(length);
+                self.bytes.extend_from_slice(&code.code);
+                self.table(
+                    "exception handlers",
+                    &code.exception_table,
+                    |writer, handler| {
+                        writer.u16(handler.start_pc);
+                        writer.u16(handler.end_pc);
+                        writer.u16(handler.handler_pc);
+                        writer.u16(handler.catch_type.as_ref().map_or(0, |class| class.index));
+                    },
+                );
+                self.attributes(&code.attributes);
+            }
+            Attribute::Exceptions(classes)
+            | Attribute::NestMembers(classes)
+            | Attribute::PermittedSubclasses(classes) => {
+                self.table("classes", classes, |writer, class| writer.u16(class.index));
+            }
+            Attribute::InnerClasses(inner_classes) => {
+                self.table("inner classes", inner_classes, |writer, inner| {
+                    writer.u16(inner.inner_class.index);
+                    writer.u16(inner.outer_class_info_index);
+                    writer.u16(inner.inner_name_index);
+                    writer.u16(inner.inner_class_access_flags);
+                });
+            }
+            Attribute::EnclosingMethod {
+                class,
+                method_index,
+            } => {
+                self.u16(class.index);
+                self.u16(*method_index);
+            }
+            Attribute::Synthetic | Attribute::Deprecated => {}
+            Attribute::LineNumberTable(lines) => {
+                self.table("line numbers", lines, |writer, line| {
+                    writer.u16(line.start_pc);
+                    writer.u16(line.line_number);
+                });
+            }
+            Attribute::LocalVariableTable(variables)
+            | Attribute::LocalVariableTypeTable(variables) => {
+                self.table("local variables", variables, |writer, variable| {
+                    writer.u16(variable.start_pc);
+                    writer.u16(variable.length);
+                    writer.u16(variable.name_index);
+                    writer.u16(variable.descriptor_index);
+                    writer.u16(variable.index);
+                });
+            }
+            Attribute::BootstrapMethods(methods) => {
+                self.table("bootstrap methods", methods, |writer, method| {
+                    writer.u16(method.method_ref);
+                    writer.table(
+                        "bootstrap arguments",
+                        &method.arguments,
+                        |writer, argument| writer.u16(*argument),
+                    );
+                });
+            }
+            Attribute::MethodParameters(parameters) => {
+                // Unlike every other table this one has a single byte count.
+                let count = u8::try_from(parameters.len())
+                    .unwrap_or_else(|_| panic!("too many method parameters for a class file"));
+                self.u8(count);
+                for parameter in parameters {
+                    self.u16(parameter.name_index);
+                    self.u16(parameter.access_flags);
+                }
+            }
+            Attribute::NestHost(class) => self.u16(class.index),
+            Attribute::Raw { bytes, .. } => self.bytes.extend_from_slice(bytes),
+        }
+    }
+}
+
+impl ClassFile {
+    /// Serialises the class file. A class file that was parsed and not
+    /// modified is written back byte for byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a table holds more entries than its count can express,
+    /// which can only happen after the class file was modified.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer { bytes: Vec::new() };
+        writer.bytes.extend_from_slice(&MAGIC);
+        writer.u16(self.minor_version);
+        writer.u16(self.major_version);
+        writer.constant_pool(&self.constant_pool);
+        writer.u16(self.access_flags);
+        writer.u16(self.this_class);
+        writer.u16(self.super_class);
+        writer.table("interfaces", &self.interfaces, |writer, interface| {
+            writer.u16(interface.index)
+        });
+        writer.table("fields", &self.fields, |writer, field| {
+            writer.u16(field.access_flags);
+            writer.u16(field.name_index);
+            writer.u16(field.descriptor_index);
+            writer.attributes(&field.attributes);
+        });
+        writer.table("methods", &self.methods, |writer, method| {
+            writer.u16(method.access_flags);
+            writer.u16(method.name_index);
+            writer.u16(method.descriptor_index);
+            writer.attributes(&method.attributes);
+        });
+        writer.attributes(&self.attributes);
+        writer.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ClassBuilder;
+
+    const PACKAGE_INFO: &[u8] = include_bytes!("../../package-info.class");
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/Sample.class");
+
+    fn round_trip(bytes: &[u8]) {
+        let class_file = ClassFile::parse(bytes).unwrap();
+        assert_eq!(class_file.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn writes_back_compiled_classes() {
+        round_trip(PACKAGE_INFO);
+        round_trip(SAMPLE);
+    }
+
+    #[test]
+    fn writes_back_wide_constants_and_unpaired_surrogates() {
+        let mut class = ClassBuilder::new(52);
+        let long = class.long(i64::MIN);
+        // A NaN other than the one Java uses for Double.NaN.
+        class.double(f64::from_bits(0x7FF0_0000_0000_0001));
+        let name = class.utf8("value");
+        // An unpaired high surrogate between "a" and "b", and U+0000.
+        let odd = class.utf8_bytes(&[0x61, 0xED, 0xA0, 0xBD, 0x62, 0xC0, 0x80]);
+        let constant_value = class.attribute("ConstantValue", &long.to_be_bytes());
+        let descriptor = class.utf8("J");
+        class.field_at(0x0018, name, descriptor, &[constant_value]);
+        let signature = class.attribute("Signature", &odd.to_be_bytes());
+        class.field_at(0x0000, odd, descriptor, &[signature]);
+        let code = class.code(2, 1, &[0x14, 0x00, long as u8, 0xAD], &[]);
+        class.method(0x0009, "get", "()J", &[code]);
+        let custom = class.attribute("Custom", &[0xCA, 0xFE]);
+        class.class_attribute(custom);
+        round_trip(&class.build());
+    }
+}